@@ -0,0 +1,695 @@
+use amethyst::{
+    ecs::Entity,
+    core::nalgebra::Vector2,
+};
+
+use crate::geometry::Aabb;
+
+use std::collections::{HashMap, HashSet};
+
+/// Boxes covering more cells of a 'SpatialHashGrid' are tested against every other box instead.
+const MAX_CELLS_PER_BOX: i64 = 64;
+
+/// A collider as seen by the broad phase.
+#[derive(Clone, Debug)]
+pub struct Proxy {
+    pub entity: Entity,
+    /// Bounding box of the collider in world space.
+    pub aabb: Aabb,
+    /// Static proxies belong to entities with a 'PassiveCollider', which usually don't move.
+    pub is_static: bool,
+}
+
+/// The strategy the 'CollisionSystem' uses to find pairs of colliders that might collide.
+///
+/// It's stored as a resource, which defaults to a 'SpatialHashGrid'.
+/// Choose another one with 'ColliderPhysicsBundle::with_broad_phase'.
+pub enum BroadPhase {
+    SpatialHash(SpatialHashGrid),
+    DynamicTree(DynamicTreeBroadPhase),
+    SweepAndPrune(SweepAndPrune),
+}
+
+impl BroadPhase {
+    /// Indices of pairs of proxies whose boxes overlap, lower index first, sorted.
+    /// Pairs of two static proxies may be left out.
+    pub fn pairs(&mut self, proxies: &[Proxy]) -> Vec<(usize, usize)> {
+        match self {
+            BroadPhase::SpatialHash(grid) => {
+                let aabbs: Vec<Aabb> = proxies.iter().map(|proxy| proxy.aabb).collect();
+                grid.entities = proxies.iter().map(|proxy| proxy.entity).collect();
+                grid.known = grid.entities.iter().cloned().collect();
+                grid.pairs(&aabbs)
+            }
+            BroadPhase::DynamicTree(trees) => trees.pairs(proxies),
+            BroadPhase::SweepAndPrune(sweep) => sweep.pairs(proxies),
+        }
+    }
+
+    /// Entities whose boxes might overlap 'aabb', in no particular order.
+    ///
+    /// The boxes are the ones of the last call to 'pairs', i.e. the last time the 'CollisionSystem' ran.
+    /// Colliders added or moved since then may be missed, see 'contains'.
+    pub fn query(&self, aabb: &Aabb) -> Vec<Entity> {
+        match self {
+            BroadPhase::SpatialHash(grid) => grid.query(aabb).into_iter()
+                .filter_map(|index| grid.entities.get(index).cloned())
+                .collect(),
+            BroadPhase::DynamicTree(trees) => trees.query(aabb),
+            BroadPhase::SweepAndPrune(sweep) => sweep.query(aabb),
+        }
+    }
+
+    /// Whether the last call to 'pairs' had a proxy for 'entity'.
+    pub fn contains(&self, entity: Entity) -> bool {
+        match self {
+            BroadPhase::SpatialHash(grid) => grid.known.contains(&entity),
+            BroadPhase::DynamicTree(trees) => trees.leaves.contains_key(&entity),
+            BroadPhase::SweepAndPrune(sweep) => sweep.aabbs.contains_key(&entity),
+        }
+    }
+}
+
+impl Default for BroadPhase {
+    fn default() -> Self {
+        BroadPhase::SpatialHash(SpatialHashGrid::default())
+    }
+}
+
+/// Finds pairs of colliders that are close enough to possibly collide,
+/// so only those need to be tested thoroughly.
+///
+/// Divides the world into square cells of 'cell_size'. Colliders are only tested against colliders
+/// in the same cells. The cell size should be about the size of a typical collider.
+/// Colliders much bigger than a cell are tested against every other collider instead.
+///
+/// Works best if the colliders are about the same size, see 'DynamicTreeBroadPhase' otherwise.
+pub struct SpatialHashGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    /// Boxes of the last call to 'pairs' that cover too many cells to be put into them
+    oversized: Vec<usize>,
+    /// The boxes of the last call to 'pairs'
+    aabbs: Vec<Aabb>,
+    /// The entity of every box, if the grid is used as a 'BroadPhase'
+    entities: Vec<Entity>,
+    known: HashSet<Entity>,
+}
+
+impl SpatialHashGrid {
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0. && cell_size.is_finite(),
+                "The cell size of a spatial hash grid needs to be positive and finite, but is {}", cell_size);
+        SpatialHashGrid {
+            cell_size,
+            cells: HashMap::new(),
+            oversized: Vec::new(),
+            aabbs: Vec::new(),
+            entities: Vec::new(),
+            known: HashSet::new(),
+        }
+    }
+
+    /// Indices of all pairs of boxes that overlap, lower index first, sorted.
+    pub fn pairs(&mut self, aabbs: &[Aabb]) -> Vec<(usize, usize)> {
+        self.aabbs = aabbs.to_vec();
+        self.cells.clear();
+        self.oversized.clear();
+        for (index, aabb) in aabbs.iter().enumerate() {
+            let (min, max) = (self.cell(&aabb.min), self.cell(&aabb.max));
+            if covered_cells(min, max) > MAX_CELLS_PER_BOX {
+                self.oversized.push(index);
+                continue
+            }
+
+            for x in min.0..=max.0 {
+                for y in min.1..=max.1 {
+                    self.cells.entry((x, y)).or_insert_with(Vec::new).push(index);
+                }
+            }
+        }
+
+        // Boxes sharing several cells would be reported several times
+        let mut pairs = HashSet::new();
+        for indices in self.cells.values() {
+            for (i, first) in indices.iter().enumerate() {
+                for second in &indices[i + 1..] {
+                    if aabbs[*first].overlaps(&aabbs[*second]) {
+                        pairs.insert((*first.min(second), *first.max(second)));
+                    }
+                }
+            }
+        }
+        for first in &self.oversized {
+            for (second, aabb) in aabbs.iter().enumerate() {
+                if second != *first && aabbs[*first].overlaps(aabb) {
+                    pairs.insert((*first.min(&second), *first.max(&second)));
+                }
+            }
+        }
+
+        let mut pairs: Vec<(usize, usize)> = pairs.into_iter().collect();
+        pairs.sort();
+        pairs
+    }
+
+    /// Indices of the boxes of the last call to 'pairs' that overlap 'aabb', sorted.
+    pub fn query(&self, aabb: &Aabb) -> Vec<usize> {
+        let (min, max) = (self.cell(&aabb.min), self.cell(&aabb.max));
+
+        // Huge boxes cover more cells than are occupied, so look at the occupied ones instead
+        let mut found: HashSet<usize> = self.oversized.iter().cloned().collect();
+        if covered_cells(min, max) > self.cells.len() as i64 {
+            for (cell, indices) in self.cells.iter() {
+                if cell.0 >= min.0 && cell.0 <= max.0 && cell.1 >= min.1 && cell.1 <= max.1 {
+                    found.extend(indices);
+                }
+            }
+        } else {
+            for x in min.0..=max.0 {
+                for y in min.1..=max.1 {
+                    found.extend(self.cells.get(&(x, y)).into_iter().flatten());
+                }
+            }
+        }
+
+        let mut found: Vec<usize> = found.into_iter()
+            .filter(|index| self.aabbs[*index].overlaps(aabb))
+            .collect();
+        found.sort();
+        found
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    fn cell(&self, point: &Vector2<f32>) -> (i32, i32) {
+        ((point[0] / self.cell_size).floor() as i32, (point[1] / self.cell_size).floor() as i32)
+    }
+}
+
+/// Number of cells from 'min' to 'max', both included.
+fn covered_cells(min: (i32, i32), max: (i32, i32)) -> i64 {
+    (i64::from(max.0) - i64::from(min.0) + 1) * (i64::from(max.1) - i64::from(min.1) + 1)
+}
+
+impl Default for SpatialHashGrid {
+    fn default() -> Self {
+        SpatialHashGrid::new(64.)
+    }
+}
+
+struct TreeNode {
+    aabb: Aabb,
+    parent: Option<usize>,
+    children: Option<(usize, usize)>,
+    entity: Option<Entity>,
+}
+
+/// A bounding volume hierarchy of boxes that can be updated incrementally.
+///
+/// Leaves store boxes enlarged by 'margin', so small movements don't require changes to the tree.
+pub struct DynamicAabbTree {
+    nodes: Vec<TreeNode>,
+    free: Vec<usize>,
+    root: Option<usize>,
+    margin: f32,
+}
+
+impl DynamicAabbTree {
+    pub fn new(margin: f32) -> Self {
+        DynamicAabbTree {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: None,
+            margin,
+        }
+    }
+
+    /// Returns the id of the new leaf, which is needed to update or remove it.
+    pub fn insert(&mut self, entity: Entity, aabb: &Aabb) -> usize {
+        let leaf = self.allocate(TreeNode {
+            aabb: aabb.expanded(self.margin),
+            parent: None,
+            children: None,
+            entity: Some(entity),
+        });
+        self.insert_leaf(leaf);
+        leaf
+    }
+
+    pub fn remove(&mut self, leaf: usize) {
+        self.remove_leaf(leaf);
+        self.nodes[leaf].entity = None;
+        self.free.push(leaf);
+    }
+
+    /// Moves the leaf if 'aabb' isn't covered by its enlarged box anymore.
+    /// Returns whether the tree changed.
+    pub fn update(&mut self, leaf: usize, aabb: &Aabb) -> bool {
+        if self.nodes[leaf].aabb.contains(aabb) {
+            return false
+        }
+
+        self.remove_leaf(leaf);
+        self.nodes[leaf].aabb = aabb.expanded(self.margin);
+        self.insert_leaf(leaf);
+        true
+    }
+
+    /// Entities of all leaves whose enlarged boxes overlap 'aabb'.
+    pub fn query(&self, aabb: &Aabb) -> Vec<Entity> {
+        let mut found = Vec::new();
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.aabb.overlaps(aabb) {
+                continue
+            }
+            match node.children {
+                Some((left, right)) => {
+                    stack.push(left);
+                    stack.push(right);
+                }
+                None => found.extend(node.entity),
+            }
+        }
+
+        found
+    }
+
+    fn allocate(&mut self, node: TreeNode) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        let root = match self.root {
+            Some(root) => root,
+            None => {
+                self.nodes[leaf].parent = None;
+                self.root = Some(leaf);
+                return
+            }
+        };
+
+        // Descend into the child whose box grows the least
+        let leaf_aabb = self.nodes[leaf].aabb;
+        let mut sibling = root;
+        while let Some((left, right)) = self.nodes[sibling].children {
+            let growth = |index: usize| {
+                let aabb = &self.nodes[index].aabb;
+                aabb.merged(&leaf_aabb).perimeter() - aabb.perimeter()
+            };
+            sibling = if growth(left) <= growth(right) { left } else { right };
+        }
+
+        let old_parent = self.nodes[sibling].parent;
+        let new_parent = self.allocate(TreeNode {
+            aabb: leaf_aabb.merged(&self.nodes[sibling].aabb),
+            parent: old_parent,
+            children: Some((sibling, leaf)),
+            entity: None,
+        });
+        self.nodes[sibling].parent = Some(new_parent);
+        self.nodes[leaf].parent = Some(new_parent);
+
+        match old_parent {
+            Some(old_parent) => {
+                self.replace_child(old_parent, sibling, new_parent);
+                self.refit(old_parent);
+            }
+            None => self.root = Some(new_parent),
+        }
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        let parent = match self.nodes[leaf].parent {
+            Some(parent) => parent,
+            None => {
+                self.root = None;
+                return
+            }
+        };
+
+        let sibling = match self.nodes[parent].children {
+            Some((left, right)) if left == leaf => right,
+            Some((left, _)) => left,
+            None => unreachable!("Parent without children"),
+        };
+
+        let grandparent = self.nodes[parent].parent;
+        self.nodes[sibling].parent = grandparent;
+        match grandparent {
+            Some(grandparent) => {
+                self.replace_child(grandparent, parent, sibling);
+                self.refit(grandparent);
+            }
+            None => self.root = Some(sibling),
+        }
+
+        self.nodes[parent].children = None;
+        self.free.push(parent);
+        self.nodes[leaf].parent = None;
+    }
+
+    fn replace_child(&mut self, parent: usize, old: usize, new: usize) {
+        if let Some((left, right)) = self.nodes[parent].children {
+            self.nodes[parent].children = Some(if left == old { (new, right) } else { (left, new) });
+        }
+    }
+
+    /// Recompute the boxes from 'index' up to the root.
+    fn refit(&mut self, index: usize) {
+        let mut current = Some(index);
+        while let Some(index) = current {
+            if let Some((left, right)) = self.nodes[index].children {
+                self.nodes[index].aabb = self.nodes[left].aabb.merged(&self.nodes[right].aabb);
+            }
+            current = self.nodes[index].parent;
+        }
+    }
+}
+
+/// Keeps the proxies in two dynamic bounding volume trees, one for static and one for moving proxies.
+///
+/// Unlike the 'SpatialHashGrid', this copes well with colliders of wildly different sizes.
+/// Static proxies rarely move, so their tree is rarely changed.
+pub struct DynamicTreeBroadPhase {
+    dynamic_tree: DynamicAabbTree,
+    static_tree: DynamicAabbTree,
+    /// The leaf of each entity and whether it's in the static tree
+    leaves: HashMap<Entity, (usize, bool)>,
+}
+
+impl DynamicTreeBroadPhase {
+    /// Boxes of moving proxies are enlarged by 'margin', so they don't need to be moved in the tree
+    /// every time their collider moves a bit.
+    pub fn new(margin: f32) -> Self {
+        DynamicTreeBroadPhase {
+            dynamic_tree: DynamicAabbTree::new(margin),
+            static_tree: DynamicAabbTree::new(0.),
+            leaves: HashMap::new(),
+        }
+    }
+
+    /// Indices of pairs of proxies whose boxes overlap, lower index first, sorted.
+    /// Pairs of two static proxies are left out.
+    pub fn pairs(&mut self, proxies: &[Proxy]) -> Vec<(usize, usize)> {
+        self.synchronize(proxies);
+
+        let indices: HashMap<Entity, usize> = proxies.iter()
+            .enumerate()
+            .map(|(index, proxy)| (proxy.entity, index))
+            .collect();
+
+        let mut pairs = HashSet::new();
+        for (index, proxy) in proxies.iter().enumerate().filter(|(_, proxy)| !proxy.is_static) {
+            let candidates = self.dynamic_tree.query(&proxy.aabb).into_iter()
+                .chain(self.static_tree.query(&proxy.aabb));
+
+            for other in candidates.filter_map(|entity| indices.get(&entity)) {
+                if *other != index && proxy.aabb.overlaps(&proxies[*other].aabb) {
+                    pairs.insert((index.min(*other), index.max(*other)));
+                }
+            }
+        }
+
+        let mut pairs: Vec<(usize, usize)> = pairs.into_iter().collect();
+        pairs.sort();
+        pairs
+    }
+
+    /// Entities of all proxies whose enlarged boxes overlap 'aabb', as of the last call to 'pairs'.
+    pub fn query(&self, aabb: &Aabb) -> Vec<Entity> {
+        let mut found = self.dynamic_tree.query(aabb);
+        found.extend(self.static_tree.query(aabb));
+        found
+    }
+
+    /// Insert new proxies, move existing ones and remove the ones that are gone.
+    fn synchronize(&mut self, proxies: &[Proxy]) {
+        let current: HashSet<Entity> = proxies.iter().map(|proxy| proxy.entity).collect();
+        let removed: Vec<Entity> = self.leaves.keys()
+            .filter(|entity| !current.contains(*entity))
+            .cloned()
+            .collect();
+        for entity in removed {
+            if let Some((leaf, is_static)) = self.leaves.remove(&entity) {
+                self.tree(is_static).remove(leaf);
+            }
+        }
+
+        for proxy in proxies {
+            match self.leaves.get(&proxy.entity).cloned() {
+                Some((leaf, is_static)) if is_static == proxy.is_static => {
+                    self.tree(is_static).update(leaf, &proxy.aabb);
+                }
+                existing => {
+                    if let Some((leaf, is_static)) = existing {
+                        self.tree(is_static).remove(leaf);
+                    }
+                    let leaf = self.tree(proxy.is_static).insert(proxy.entity, &proxy.aabb);
+                    self.leaves.insert(proxy.entity, (leaf, proxy.is_static));
+                }
+            }
+        }
+    }
+
+    fn tree(&mut self, is_static: bool) -> &mut DynamicAabbTree {
+        if is_static {
+            &mut self.static_tree
+        } else {
+            &mut self.dynamic_tree
+        }
+    }
+}
+
+impl Default for DynamicTreeBroadPhase {
+    fn default() -> Self {
+        DynamicTreeBroadPhase::new(8.)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Endpoint {
+    entity: Entity,
+    value: f32,
+    is_min: bool,
+}
+
+impl Endpoint {
+    /// Minimums come first if the values are equal, so touching boxes overlap.
+    fn before(&self, other: &Endpoint) -> bool {
+        self.value < other.value || (self.value == other.value && self.is_min && !other.is_min)
+    }
+}
+
+/// Keeps the bounds of all boxes sorted along both axes and remembers which boxes overlap.
+///
+/// The sorted lists and overlapping pairs persist between frames. As most colliders only move a bit
+/// every frame, the lists are nearly sorted already and only few overlaps start or end.
+/// Colliders that teleport across the world or lots of colliders lined up along an axis
+/// make this slow.
+#[derive(Default)]
+pub struct SweepAndPrune {
+    axes: [Vec<Endpoint>; 2],
+    aabbs: HashMap<Entity, Aabb>,
+    overlapping: HashSet<(Entity, Entity)>,
+}
+
+impl SweepAndPrune {
+    /// Indices of pairs of proxies whose boxes overlap, lower index first, sorted.
+    pub fn pairs(&mut self, proxies: &[Proxy]) -> Vec<(usize, usize)> {
+        let indices: HashMap<Entity, usize> = proxies.iter()
+            .enumerate()
+            .map(|(index, proxy)| (proxy.entity, index))
+            .collect();
+
+        // Forget proxies that are gone
+        if self.aabbs.keys().any(|entity| !indices.contains_key(entity)) {
+            for axis in self.axes.iter_mut() {
+                axis.retain(|endpoint| indices.contains_key(&endpoint.entity));
+            }
+            self.overlapping.retain(|(first, second)| indices.contains_key(first) && indices.contains_key(second));
+            self.aabbs.retain(|entity, _| indices.contains_key(entity));
+        }
+
+        // New proxies are sorted into the lists from the end
+        for proxy in proxies {
+            if self.aabbs.insert(proxy.entity, proxy.aabb).is_none() {
+                for (axis, endpoints) in self.axes.iter_mut().enumerate() {
+                    endpoints.push(Endpoint { entity: proxy.entity, value: proxy.aabb.min[axis], is_min: true });
+                    endpoints.push(Endpoint { entity: proxy.entity, value: proxy.aabb.max[axis], is_min: false });
+                }
+            }
+        }
+
+        for axis in 0..2 {
+            for endpoint in self.axes[axis].iter_mut() {
+                let aabb = &self.aabbs[&endpoint.entity];
+                endpoint.value = if endpoint.is_min { aabb.min[axis] } else { aabb.max[axis] };
+            }
+            self.sort_axis(axis);
+        }
+
+        let mut pairs: Vec<(usize, usize)> = self.overlapping.iter()
+            .map(|(first, second)| {
+                let (first, second) = (indices[first], indices[second]);
+                (first.min(second), first.max(second))
+            })
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    /// Entities of all proxies whose boxes overlap 'aabb', as of the last call to 'pairs'.
+    pub fn query(&self, aabb: &Aabb) -> Vec<Entity> {
+        // Boxes starting to the right of 'aabb' can't overlap it
+        self.axes[0].iter()
+            .take_while(|endpoint| endpoint.value <= aabb.max[0])
+            .filter(|endpoint| endpoint.is_min && self.aabbs[&endpoint.entity].overlaps(aabb))
+            .map(|endpoint| endpoint.entity)
+            .collect()
+    }
+
+    /// Insertion sort, which is fast for nearly sorted lists.
+    /// Overlaps can only start or end when two bounds swap places.
+    fn sort_axis(&mut self, axis: usize) {
+        for i in 1..self.axes[axis].len() {
+            let mut j = i;
+            while j > 0 && self.axes[axis][j].before(&self.axes[axis][j - 1]) {
+                let (moving, passed) = (self.axes[axis][j], self.axes[axis][j - 1]);
+                let pair = (moving.entity.min(passed.entity), moving.entity.max(passed.entity));
+
+                if moving.is_min && !passed.is_min {
+                    if self.aabbs[&moving.entity].overlaps(&self.aabbs[&passed.entity]) {
+                        self.overlapping.insert(pair);
+                    }
+                } else if !moving.is_min && passed.is_min {
+                    self.overlapping.remove(&pair);
+                }
+
+                self.axes[axis].swap(j, j - 1);
+                j -= 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_broad_phase {
+    use crate::broad_phase::{SpatialHashGrid, DynamicTreeBroadPhase, SweepAndPrune, Proxy};
+    use crate::geometry::Aabb;
+    use amethyst::core::nalgebra::Vector2;
+    use amethyst::ecs::{World, Builder};
+
+    fn aabb(min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Aabb {
+        Aabb::new(Vector2::new(min_x, min_y), Vector2::new(max_x, max_y))
+    }
+
+    #[test]
+    fn test_spatial_hash_grid() {
+        let aabbs = [
+            aabb(0., 0., 10., 10.),
+            aabb(5., 5., 15., 15.),
+            // Spans many cells, overlaps the first and the last box
+            aabb(-100., -100., 1., 1.),
+            aabb(200., 200., 210., 210.),
+            aabb(-90., -90., -80., -80.),
+        ];
+
+        assert_eq!(SpatialHashGrid::new(8.).pairs(&aabbs), vec![(0, 1), (0, 2), (2, 4)]);
+        let mut grid = SpatialHashGrid::new(1000.);
+        assert_eq!(grid.pairs(&aabbs), vec![(0, 1), (0, 2), (2, 4)]);
+
+        assert_eq!(grid.query(&aabb(-1., -1., 6., 6.)), vec![0, 1, 2]);
+        // Covers more cells than are occupied
+        assert_eq!(grid.query(&aabb(150., 150., 100000., 100000.)), vec![3]);
+
+        // A box spanning billions of cells is tested against every other box instead
+        let mut grid = SpatialHashGrid::new(1.);
+        let huge = [aabb(-1e9, -1e9, 1e9, 1e9), aabb(0., 0., 0.5, 0.5), aabb(2., 2., 3., 3.)];
+        assert_eq!(grid.pairs(&huge), vec![(0, 1), (0, 2)]);
+        assert_eq!(grid.query(&aabb(2.5, 2.5, 2.6, 2.6)), vec![0, 2]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_spatial_hash_grid_cell_size() {
+        SpatialHashGrid::new(0.);
+    }
+
+    fn proxies(world: &mut World) -> Vec<Proxy> {
+        [
+            (aabb(0., 0., 10., 10.), false),
+            (aabb(5., 5., 15., 15.), false),
+            // Huge static box, overlaps the first box and the other static ones
+            (aabb(-1000., -1000., 1., 1.), true),
+            (aabb(200., 200., 210., 210.), false),
+            (aabb(-90., -90., -80., -80.), true),
+            (aabb(-85., -85., -70., -70.), true),
+        ].iter()
+            .map(|(aabb, is_static)| Proxy {
+                entity: world.create_entity().build(),
+                aabb: *aabb,
+                is_static: *is_static,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dynamic_tree() {
+        let mut world = World::new();
+        let mut proxies = proxies(&mut world);
+
+        let mut broad_phase = DynamicTreeBroadPhase::new(2.);
+        // Overlapping static boxes aren't reported
+        assert_eq!(broad_phase.pairs(&proxies), vec![(0, 1), (0, 2)]);
+
+        proxies[3].aabb = aabb(-80., -80., -75., -75.);
+        assert_eq!(broad_phase.pairs(&proxies), vec![(0, 1), (0, 2), (2, 3), (3, 4), (3, 5)]);
+
+        proxies.remove(0);
+        assert_eq!(broad_phase.pairs(&proxies), vec![(1, 2), (2, 3), (2, 4)]);
+
+        let mut found = broad_phase.query(&aabb(-88., -88., -87., -87.));
+        found.sort();
+        assert_eq!(found, vec![proxies[1].entity, proxies[3].entity]);
+    }
+
+    #[test]
+    fn test_sweep_and_prune() {
+        let mut world = World::new();
+        let mut proxies = proxies(&mut world);
+
+        let mut broad_phase = SweepAndPrune::default();
+        assert_eq!(broad_phase.pairs(&proxies), vec![(0, 1), (0, 2), (2, 4), (2, 5), (4, 5)]);
+
+        proxies[3].aabb = aabb(-80., -80., -75., -75.);
+        assert_eq!(broad_phase.pairs(&proxies),
+                   vec![(0, 1), (0, 2), (2, 3), (2, 4), (2, 5), (3, 4), (3, 5), (4, 5)]);
+
+        // Only apart along one axis
+        proxies[1].aabb = aabb(5., 20., 15., 30.);
+        assert_eq!(broad_phase.pairs(&proxies),
+                   vec![(0, 2), (2, 3), (2, 4), (2, 5), (3, 4), (3, 5), (4, 5)]);
+
+        proxies.remove(0);
+        assert_eq!(broad_phase.pairs(&proxies), vec![(1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)]);
+
+        let mut found = broad_phase.query(&aabb(6., 21., 7., 22.));
+        found.sort();
+        assert_eq!(found, vec![proxies[0].entity]);
+    }
+}
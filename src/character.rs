@@ -0,0 +1,377 @@
+use amethyst::{
+    ecs::Entity,
+    core::nalgebra::Vector2,
+};
+
+use crate::{
+    components::{CharacterController2D, Collider2D, ColliderContact, OneWayPlatform},
+    geometry::Aabb,
+};
+
+/// Distance a character keeps to the colliders it touches, so it can slide along them.
+const SKIN: f32 = 0.02;
+/// How often a single move can hit something and change direction.
+const MAX_SLIDES: usize = 4;
+
+/// Outcome of 'move_and_slide'.
+#[derive(Clone, Debug)]
+pub struct CharacterMove {
+    /// How far the character actually moved, in world space.
+    pub displacement: Vector2<f32>,
+    pub grounded: bool,
+    pub ground_normal: Option<Vector2<f32>>,
+    pub on_ceiling: bool,
+    pub on_wall: bool,
+    pub touched: Vec<Entity>,
+}
+
+impl CharacterMove {
+    /// Stores the state of the move in the controller.
+    pub fn apply_to(self, controller: &mut CharacterController2D) {
+        controller.grounded = self.grounded;
+        controller.ground_normal = self.ground_normal;
+        controller.on_ceiling = self.on_ceiling;
+        controller.on_wall = self.on_wall;
+        controller.touched = self.touched;
+    }
+
+    /// Records that the character touched 'entity', 'normal' pointing from the character towards it.
+    /// Walls are left to the caller, as the character might step onto them.
+    fn touch(&mut self, controller: &CharacterController2D, entity: Entity, normal: &Vector2<f32>) -> Surface {
+        if !self.touched.contains(&entity) {
+            self.touched.push(entity);
+        }
+
+        let surface = surface(controller, normal);
+        match surface {
+            Surface::Floor => {
+                self.grounded = true;
+                self.ground_normal = Some(-normal);
+            }
+            Surface::Ceiling => self.on_ceiling = true,
+            Surface::Wall => (),
+        }
+        surface
+    }
+}
+
+/// A collider in world space that stops characters.
+#[derive(Clone)]
+pub struct Obstacle<'a> {
+    pub entity: Entity,
+    pub collider: &'a Collider2D,
+    pub position: Vector2<f32>,
+    /// How far the obstacle moves this frame, in world space.
+    pub displacement: Vector2<f32>,
+    /// Characters pass through one-way platforms from the open side.
+    pub platform: Option<&'a OneWayPlatform>,
+}
+
+impl<'a> Obstacle<'a> {
+    pub fn new(entity: Entity, collider: &'a Collider2D, position: Vector2<f32>) -> Self {
+        Obstacle {
+            entity,
+            collider,
+            position,
+            displacement: Vector2::new(0., 0.),
+            platform: None,
+        }
+    }
+
+    /// Whether a character moving along 'motion' passes through the obstacle,
+    /// 'normal' pointing from the character towards it.
+    fn lets_pass(&self, normal: &Vector2<f32>, motion: &Vector2<f32>) -> bool {
+        self.platform.map_or(false, |platform| platform.lets_pass(&-normal, &(motion - self.displacement)))
+    }
+}
+
+enum Surface {
+    Floor,
+    Wall,
+    Ceiling,
+}
+
+/// Classifies what the character hit, 'normal' pointing from the character towards it.
+fn surface(controller: &CharacterController2D, normal: &Vector2<f32>) -> Surface {
+    let upwards = -normal.dot(&controller.up);
+    let limit = controller.max_slope_angle.cos();
+
+    if upwards >= limit {
+        Surface::Floor
+    } else if -upwards >= limit {
+        Surface::Ceiling
+    } else {
+        Surface::Wall
+    }
+}
+
+/// The first obstacle 'collider' hits when moving along 'motion', with the time of impact and the normal.
+fn cast(collider: &Collider2D, position: &Vector2<f32>, motion: &Vector2<f32>,
+        obstacles: &[Obstacle]) -> Option<(f32, Vector2<f32>, Entity)> {
+    obstacles.iter()
+        .filter_map(|obstacle| collider.time_of_impact(position, motion, obstacle.collider, &obstacle.position)
+            .filter(|(_, normal)| !obstacle.lets_pass(normal, motion))
+            .map(|(time, normal)| (time, normal, obstacle.entity)))
+        .fold(None, |earliest: Option<(f32, Vector2<f32>, Entity)>, candidate| match earliest {
+            Some(earliest) if earliest.0 <= candidate.0 => Some(earliest),
+            _ => Some(candidate),
+        })
+}
+
+/// Moves as far along 'motion' as possible, staying 'SKIN' away from whatever is hit.
+fn advance(collider: &Collider2D, position: &Vector2<f32>, motion: &Vector2<f32>,
+           obstacles: &[Obstacle]) -> (Vector2<f32>, Option<(f32, Vector2<f32>, Entity)>) {
+    match cast(collider, position, motion, obstacles) {
+        Some(hit) => (position + motion * hit.0 - hit.1 * SKIN, Some(hit)),
+        None => (position + motion, None),
+    }
+}
+
+/// Pushes the character out of the obstacles it overlaps, deepest overlap first,
+/// e.g. when a moving platform moved into it. One-way platforms the character moves through along
+/// 'motion' are left alone.
+fn depenetrate(controller: &CharacterController2D, collider: &Collider2D, position: &Vector2<f32>,
+               motion: &Vector2<f32>, obstacles: &[Obstacle], result: &mut CharacterMove) -> Vector2<f32> {
+    let mut current = *position;
+    for _ in 0..MAX_SLIDES {
+        let deepest = obstacles.iter()
+            .flat_map(|obstacle| collider.contacts(&current, obstacle.collider, &obstacle.position).into_iter()
+                .filter(move |contact| !obstacle.lets_pass(&contact.normal, motion))
+                .map(move |contact| (obstacle.entity, contact)))
+            .fold(None, |deepest: Option<(Entity, ColliderContact)>, candidate| match deepest {
+                Some(deepest) if deepest.1.depth >= candidate.1.depth => Some(deepest),
+                _ => Some(candidate),
+            });
+
+        let (entity, contact) = match deepest {
+            Some(deepest) => deepest,
+            None => break,
+        };
+        current -= contact.normal * (contact.depth + SKIN);
+        if let Surface::Wall = result.touch(controller, entity, &contact.normal) {
+            result.on_wall = true;
+        }
+    }
+    current
+}
+
+/// Tries to climb onto an obstacle no higher than the step height while moving along 'motion'.
+/// Returns the position on top of it and the part of 'motion' that's left.
+fn step(controller: &CharacterController2D, collider: &Collider2D, position: &Vector2<f32>, motion: &Vector2<f32>,
+        obstacles: &[Obstacle]) -> Option<(Vector2<f32>, Vector2<f32>)> {
+    let up = controller.up;
+    let horizontal = motion - up * motion.dot(&up);
+    if controller.step_height <= 0. || horizontal.norm() <= SKIN {
+        return None
+    }
+
+    let (raised, _) = advance(collider, position, &(up * controller.step_height), obstacles);
+    let (forward, blocked) = advance(collider, &raised, &horizontal, obstacles);
+    if (forward - raised).norm() <= SKIN {
+        // Still blocked, the obstacle is too high
+        return None
+    }
+
+    let rise = (raised - position).dot(&up);
+    match advance(collider, &forward, &(-up * (rise + 2. * SKIN)), obstacles) {
+        (landed, Some((_, normal, _))) => match surface(controller, &normal) {
+            Surface::Floor => {
+                let moved = blocked.map_or(1., |(time, _, _)| time);
+                Some((landed, horizontal * (1. - moved) + up * motion.dot(&up)))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Moves 'collider', whose entity is at 'position', by 'displacement' through 'obstacles'.
+///
+/// A character that starts out overlapping an obstacle is pushed out of it first.
+/// One-way platforms only stop characters moving into them from their solid side, see 'OneWayPlatform::lets_pass'.
+/// When it hits something, the rest of the displacement is projected onto the surface that was hit,
+/// so the character slides along walls and slopes. Obstacles no higher than the step height
+/// are climbed if the character is grounded. Afterwards, the character looks for a floor right below it.
+pub fn move_and_slide(controller: &CharacterController2D, collider: &Collider2D, position: &Vector2<f32>,
+                      displacement: &Vector2<f32>, obstacles: &[Obstacle]) -> CharacterMove {
+    let mut result = CharacterMove {
+        displacement: Vector2::new(0., 0.),
+        grounded: false,
+        ground_normal: None,
+        on_ceiling: false,
+        on_wall: false,
+        touched: Vec::new(),
+    };
+
+    let mut current = depenetrate(controller, collider, position, displacement, obstacles, &mut result);
+    let mut remaining = *displacement;
+    for _ in 0..MAX_SLIDES {
+        if remaining.norm() <= std::f32::EPSILON {
+            break
+        }
+
+        let (next, hit) = advance(collider, &current, &remaining, obstacles);
+        let (time, normal, entity) = match hit {
+            Some(hit) => hit,
+            None => {
+                current = next;
+                break
+            }
+        };
+
+        let left = remaining * (1. - time);
+        if let Surface::Wall = result.touch(controller, entity, &normal) {
+            let stepped = if controller.grounded || result.grounded {
+                step(controller, collider, &next, &left, obstacles)
+            } else {
+                None
+            };
+            if let Some((stepped, rest)) = stepped {
+                current = stepped;
+                remaining = rest;
+                continue
+            }
+            result.on_wall = true;
+        }
+
+        current = next;
+        remaining = left - normal * left.dot(&normal);
+    }
+
+    if !result.grounded {
+        if let Some((_, normal, _)) = cast(collider, &current, &(-controller.up * (2. * SKIN)), obstacles) {
+            if let Surface::Floor = surface(controller, &normal) {
+                result.grounded = true;
+                result.ground_normal = Some(-normal);
+            }
+        }
+    }
+
+    result.displacement = current - position;
+    result
+}
+
+/// Bounding box of everything 'move_and_slide' might touch while moving 'collider' from 'position'
+/// by 'displacement', including stepping up onto obstacles.
+pub fn move_bounds(controller: &CharacterController2D, collider: &Collider2D, position: &Vector2<f32>,
+                   displacement: &Vector2<f32>) -> Aabb {
+    collider.aabb(position)
+        .merged(&collider.aabb(&(position + displacement)))
+        .expanded(controller.step_height.max(0.) + SKIN * 2.)
+}
+
+#[cfg(test)]
+mod test_character {
+    use crate::character::{move_and_slide, Obstacle};
+    use crate::components::{CharacterController2D, Collider2D, OneWayPlatform};
+    use amethyst::core::nalgebra::Vector2;
+    use amethyst::ecs::{World, Builder};
+
+    #[test]
+    fn test_slide() {
+        let mut world = World::new();
+        let (floor, wall) = (world.create_entity().build(), world.create_entity().build());
+        let (floor_collider, wall_collider) = (Collider2D::rect_without_offset(100., 2.),
+                                               Collider2D::rect_without_offset(2., 100.));
+        let obstacles = [
+            Obstacle::new(floor, &floor_collider, Vector2::new(0., -1.)),
+            Obstacle::new(wall, &wall_collider, Vector2::new(4., 50.)),
+        ];
+
+        let character = Collider2D::rect_without_offset(1., 2.);
+        let controller = CharacterController2D::default();
+
+        // Falls onto the floor and slides along it towards the wall
+        let result = move_and_slide(&controller, &character, &Vector2::new(0., 1.02),
+                                    &Vector2::new(5., -1.), &obstacles);
+        assert!((result.displacement - Vector2::new(2.48, 0.)).norm() < 0.05);
+        assert!(result.grounded && result.on_wall && !result.on_ceiling);
+        assert_eq!(result.ground_normal.map(|normal| normal.map(f32::round)), Some(Vector2::new(0., 1.)));
+        assert_eq!(result.touched, vec![floor, wall]);
+
+        // Slides up along the wall
+        let result = move_and_slide(&controller, &character, &Vector2::new(2., 5.),
+                                    &Vector2::new(3., 3.), &obstacles);
+        assert!((result.displacement - Vector2::new(0.48, 3.)).norm() < 0.05);
+        assert!(!result.grounded && result.on_wall);
+    }
+
+    #[test]
+    fn test_depenetration() {
+        let mut world = World::new();
+        let floor = world.create_entity().build();
+        let floor_collider = Collider2D::rect_without_offset(100., 2.);
+        let obstacles = [Obstacle::new(floor, &floor_collider, Vector2::new(0., -1.))];
+
+        let character = Collider2D::rect_without_offset(1., 2.);
+        let controller = CharacterController2D::default();
+
+        // Starts 0.3 deep in the floor, e.g. after the floor moved up
+        let result = move_and_slide(&controller, &character, &Vector2::new(0., 0.7),
+                                    &Vector2::new(1., -1.), &obstacles);
+        assert!((result.displacement - Vector2::new(1., 0.32)).norm() < 0.05);
+        assert!(result.grounded && !result.on_wall);
+        assert_eq!(result.touched, vec![floor]);
+    }
+
+    #[test]
+    fn test_step() {
+        let mut world = World::new();
+        let (floor, obstacle) = (world.create_entity().build(), world.create_entity().build());
+        let (floor_collider, obstacle_collider) = (Collider2D::rect_without_offset(100., 2.),
+                                                   Collider2D::rect_without_offset(2., 0.3));
+        let obstacles = [
+            Obstacle::new(floor, &floor_collider, Vector2::new(0., -1.)),
+            Obstacle::new(obstacle, &obstacle_collider, Vector2::new(3., 0.15)),
+        ];
+
+        let character = Collider2D::rect_without_offset(1., 2.);
+        let mut controller = CharacterController2D::new(std::f32::consts::PI * 0.25, 0.5);
+        controller.grounded = true;
+
+        let result = move_and_slide(&controller, &character, &Vector2::new(0., 1.02),
+                                    &Vector2::new(5., 0.), &obstacles);
+        assert!((result.displacement[0] - 4.98).abs() < 0.05);
+        assert!(result.displacement[1].abs() < 0.05);
+        assert!(result.grounded && !result.on_wall);
+
+        // Too high
+        controller.step_height = 0.2;
+        let result = move_and_slide(&controller, &character, &Vector2::new(0., 1.02),
+                                    &Vector2::new(5., 0.), &obstacles);
+        assert!((result.displacement[0] - 1.48).abs() < 0.05);
+        assert!(result.on_wall);
+    }
+
+    #[test]
+    fn test_one_way_platform() {
+        let mut world = World::new();
+        let platform_entity = world.create_entity().build();
+        let (platform_collider, platform) = (Collider2D::rect_without_offset(4., 0.5),
+                                             OneWayPlatform::new(Vector2::new(0., 1.)));
+        let mut obstacle = Obstacle::new(platform_entity, &platform_collider, Vector2::new(0., 3.));
+        obstacle.platform = Some(&platform);
+        let obstacles = [obstacle];
+
+        let character = Collider2D::rect_without_offset(1., 2.);
+        let controller = CharacterController2D::default();
+
+        // Jumps up through it
+        let result = move_and_slide(&controller, &character, &Vector2::new(0., 1.5),
+                                    &Vector2::new(0., 3.), &obstacles);
+        assert_eq!(result.displacement, Vector2::new(0., 3.));
+        assert!(!result.on_ceiling && result.touched.is_empty());
+
+        // Halfway through it, still moving up
+        let result = move_and_slide(&controller, &character, &Vector2::new(0., 2.5),
+                                    &Vector2::new(0., 1.), &obstacles);
+        assert_eq!(result.displacement, Vector2::new(0., 1.));
+
+        // Lands on it from above
+        let result = move_and_slide(&controller, &character, &Vector2::new(0., 4.27),
+                                    &Vector2::new(0., -2.), &obstacles);
+        assert!(result.displacement.norm() < 0.05);
+        assert!(result.grounded);
+        assert_eq!(result.touched, vec![platform_entity]);
+    }
+}
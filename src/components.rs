@@ -1,8 +1,15 @@
 use amethyst::ecs::{
-    Component, DenseVecStorage, NullStorage,
+    Component, DenseVecStorage, NullStorage, Entity,
 };
-use amethyst::core::nalgebra::{
-    Vector2,
+use amethyst::core::{
+    nalgebra::{Vector2, Matrix4},
+    transform::Transform,
+    timing::Time,
+};
+
+use crate::{
+    geometry::{self, Hull, Aabb},
+    utils::rotate_vec,
 };
 
 #[derive(Clone)]
@@ -11,11 +18,114 @@ pub enum Shape {
         width: f32,
         height: f32,
     },
+    Circle {
+        radius: f32,
+    },
+    /// Vertices are relative to the collider's center and need to form a convex polygon,
+    /// ordered either clockwise or counter-clockwise.
+    ConvexPolygon(Vec<Vector2<f32>>),
+    /// A vertical segment from '-half_height' to 'half_height', inflated by 'radius'.
+    Capsule {
+        half_height: f32,
+        radius: f32,
+    },
+    /// A line segment between two points relative to the collider's center.
+    Segment {
+        start: Vector2<f32>,
+        end: Vector2<f32>,
+    },
+    /// An open polyline through the vertices, relative to the collider's center.
+    /// Collisions report the index of the hit segment, where segment 'i'
+    /// goes from vertex 'i' to vertex 'i + 1'.
+    Chain(Vec<Vector2<f32>>),
+    /// Several colliders attached to the same entity, see ['Collider2D::compound'].
+    Compound(Vec<Collider2D>),
+}
+
+impl Shape {
+    /// Scale the shape along the x- and y-axis.
+    ///
+    /// Circles can't be stretched, so their radius is scaled by the larger of both factors.
+    pub fn scaled_by(&self, x: f32, y: f32) -> Shape {
+        match *self {
+            Shape::Rectangle { width, height } => Shape::Rectangle {
+                width: width * x,
+                height: height * y,
+            },
+            Shape::Circle { radius } => Shape::Circle {
+                radius: radius * x.abs().max(y.abs()),
+            },
+            Shape::ConvexPolygon(ref vertices) => Shape::ConvexPolygon(
+                vertices.iter()
+                    .map(|vertex| Vector2::new(vertex.x * x, vertex.y * y))
+                    .collect()
+            ),
+            Shape::Capsule { half_height, radius } => {
+                // Keep the overall extents of the capsule, not just the ones of its segment
+                let scaled_radius = radius * x.abs();
+                Shape::Capsule {
+                    half_height: ((half_height + radius) * y.abs() - scaled_radius).max(0.),
+                    radius: scaled_radius,
+                }
+            }
+            Shape::Segment { start, end } => Shape::Segment {
+                start: Vector2::new(start.x * x, start.y * y),
+                end: Vector2::new(end.x * x, end.y * y),
+            },
+            Shape::Chain(ref vertices) => Shape::Chain(
+                vertices.iter()
+                    .map(|vertex| Vector2::new(vertex.x * x, vertex.y * y))
+                    .collect()
+            ),
+            Shape::Compound(ref children) => Shape::Compound(
+                children.iter()
+                    .map(|child| child.scaled_by(x, y))
+                    .collect()
+            ),
+        }
+    }
+
+    /// The shape as hulls in world space, with its center at 'center' and rotated by 'rotation'.
+    ///
+    /// Chains consist of one hull per segment, compound shapes of the hulls of their children.
+    /// Every other shape is a single hull.
+    pub fn hulls(&self, center: &Vector2<f32>, rotation: f32) -> Vec<Hull> {
+        let place = |vertex: &Vector2<f32>| center + rotate_vec(vertex, rotation);
+
+        match *self {
+            Shape::Rectangle { width, height } => {
+                let (half_width, half_height) = (width.abs() * 0.5, height.abs() * 0.5);
+                vec![Hull::new(vec![
+                    place(&Vector2::new(-half_width, -half_height)),
+                    place(&Vector2::new(half_width, -half_height)),
+                    place(&Vector2::new(half_width, half_height)),
+                    place(&Vector2::new(-half_width, half_height)),
+                ], 0.)]
+            }
+            Shape::Circle { radius } => vec![Hull::new(vec![*center], radius.abs())],
+            Shape::ConvexPolygon(ref vertices) => vec![Hull::new(
+                vertices.iter().map(place).collect(), 0.
+            )],
+            Shape::Capsule { half_height, radius } => vec![Hull::new(vec![
+                place(&Vector2::new(0., -half_height.abs())),
+                place(&Vector2::new(0., half_height.abs())),
+            ], radius.abs())],
+            Shape::Segment { ref start, ref end } => vec![Hull::new(vec![place(start), place(end)], 0.)],
+            Shape::Chain(ref vertices) => vertices.windows(2)
+                .map(|segment| Hull::new(vec![place(&segment[0]), place(&segment[1])], 0.))
+                .collect(),
+            Shape::Compound(ref children) => children.iter()
+                .flat_map(|child| child.shape.hulls(&place(&child.offset), rotation + child.rotation))
+                .collect(),
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Collider2D {
     pub offset: Vector2<f32>,
+    /// Rotation of the shape around its center, in radians.
+    pub rotation: f32,
     pub shape: Shape,
 }
 
@@ -23,6 +133,7 @@ impl Collider2D {
     pub fn rect(width: f32, height: f32, offset: Vector2<f32>) -> Self {
         Collider2D {
             offset,
+            rotation: 0.,
             shape: Shape::Rectangle { width, height}
         }
     }
@@ -31,23 +142,180 @@ impl Collider2D {
         Collider2D::rect(width, height, Vector2::new(0., 0.))
     }
 
+    pub fn circle(radius: f32, offset: Vector2<f32>) -> Self {
+        Collider2D {
+            offset,
+            rotation: 0.,
+            shape: Shape::Circle { radius }
+        }
+    }
+
+    pub fn circle_without_offset(radius: f32) -> Self {
+        Collider2D::circle(radius, Vector2::new(0., 0.))
+    }
 
+    /// The vertices need to form a convex polygon, see ['Shape::ConvexPolygon'].
+    pub fn convex_polygon(vertices: Vec<Vector2<f32>>, offset: Vector2<f32>) -> Self {
+        Collider2D {
+            offset,
+            rotation: 0.,
+            shape: Shape::ConvexPolygon(vertices),
+        }
+    }
+
+    pub fn convex_polygon_without_offset(vertices: Vec<Vector2<f32>>) -> Self {
+        Collider2D::convex_polygon(vertices, Vector2::new(0., 0.))
+    }
+
+    pub fn capsule(half_height: f32, radius: f32, offset: Vector2<f32>) -> Self {
+        Collider2D {
+            offset,
+            rotation: 0.,
+            shape: Shape::Capsule { half_height, radius },
+        }
+    }
+
+    pub fn capsule_without_offset(half_height: f32, radius: f32) -> Self {
+        Collider2D::capsule(half_height, radius, Vector2::new(0., 0.))
+    }
+
+    pub fn segment(start: Vector2<f32>, end: Vector2<f32>, offset: Vector2<f32>) -> Self {
+        Collider2D {
+            offset,
+            rotation: 0.,
+            shape: Shape::Segment { start, end },
+        }
+    }
+
+    pub fn segment_without_offset(start: Vector2<f32>, end: Vector2<f32>) -> Self {
+        Collider2D::segment(start, end, Vector2::new(0., 0.))
+    }
+
+    /// Combine several colliders into one, e.g. for a character with separate head and body boxes.
+    ///
+    /// Every child is tested for collisions separately and collisions report
+    /// the index of the involved child.
+    pub fn compound(children: Vec<Collider2D>, offset: Vector2<f32>) -> Self {
+        Collider2D {
+            offset,
+            rotation: 0.,
+            shape: Shape::Compound(children),
+        }
+    }
+
+    pub fn compound_without_offset(children: Vec<Collider2D>) -> Self {
+        Collider2D::compound(children, Vector2::new(0., 0.))
+    }
+
+    /// Chains are meant for level geometry, which usually doesn't move.
+    /// Consider marking them with the ['PassiveCollider'] component.
+    pub fn chain(vertices: Vec<Vector2<f32>>, offset: Vector2<f32>) -> Self {
+        Collider2D {
+            offset,
+            rotation: 0.,
+            shape: Shape::Chain(vertices),
+        }
+    }
+
+    pub fn chain_without_offset(vertices: Vec<Vector2<f32>>) -> Self {
+        Collider2D::chain(vertices, Vector2::new(0., 0.))
+    }
+
+    /// Scale the collider along the x- and y-axis.
+    ///
+    /// The shape is scaled along its own axes, so stretching a rotated shape doesn't shear it.
     pub fn scaled_by(&self, x: f32, y: f32) -> Collider2D {
-        Collider2D::rect(self.width() * x, self.height() * y,
-                         Vector2::new(self.offset.x * x, self.offset.y * y))
+        Collider2D {
+            offset: Vector2::new(self.offset.x * x, self.offset.y * y),
+            rotation: self.rotation,
+            shape: self.shape.scaled_by(x, y),
+        }
+    }
+
+    /// Rotate the collider around the center of its entity.
+    pub fn rotated_by(&self, angle: f32) -> Collider2D {
+        Collider2D {
+            offset: rotate_vec(&self.offset, angle),
+            rotation: self.rotation + angle,
+            shape: self.shape.clone(),
+        }
+    }
+
+    /// Scale and rotate the collider like 'transform' does.
+    /// Returns the transformed collider and its entity's position.
+    pub fn transformed_by(&self, transform: &Transform) -> (Collider2D, Vector2<f32>) {
+        self.transformed_by_matrix(&transform.matrix())
+    }
+
+    /// Scale and rotate the collider like the 'matrix' of a transform does, e.g. one that
+    /// places an entity in world space. Returns the transformed collider and its entity's position.
+    pub fn transformed_by_matrix(&self, matrix: &Matrix4<f32>) -> (Collider2D, Vector2<f32>) {
+        let x_axis = Vector2::new(matrix[(0, 0)], matrix[(1, 0)]);
+        let y_axis = Vector2::new(matrix[(0, 1)], matrix[(1, 1)]);
+
+        let rotation = x_axis[1].atan2(x_axis[0]);
+        // Mirroring can't be told apart from a rotation for a single axis, so it ends up on the y-axis
+        let mirrored = x_axis[0] * y_axis[1] - x_axis[1] * y_axis[0] < 0.;
+        let scale_y = if mirrored { -y_axis.norm() } else { y_axis.norm() };
+
+        (self.scaled_by(x_axis.norm(), scale_y).rotated_by(rotation),
+         Vector2::new(matrix[(0, 3)], matrix[(1, 3)]))
+    }
+
+    /// The hulls of the shape, if its entity is at 'position'. See 'Shape::hulls'.
+    pub fn hulls(&self, position: &Vector2<f32>) -> Vec<Hull> {
+        self.shape.hulls(&(position + self.offset), self.rotation)
+    }
+
+    /// Axis aligned bounding box of the collider, if its entity is at 'position'.
+    pub fn aabb(&self, position: &Vector2<f32>) -> Aabb {
+        self.hulls(position).iter()
+            .map(|hull| hull.aabb())
+            .fold(None, |aabb: Option<Aabb>, hull_aabb| Some(match aabb {
+                Some(aabb) => aabb.merged(&hull_aabb),
+                None => hull_aabb,
+            }))
+            .unwrap_or_else(|| Aabb::new(*position, *position))
     }
 
     pub fn width(&self) -> f32 {
         match self.shape {
-            Shape::Rectangle { width, .. } => width.clone()
+            Shape::Rectangle { width, .. } => width.clone(),
+            Shape::Circle { radius } => radius * 2.,
+            Shape::ConvexPolygon(ref vertices) => extent(vertices.iter().map(|vertex| vertex.x)),
+            Shape::Capsule { radius, .. } => radius * 2.,
+            Shape::Segment { start, end } => (end.x - start.x).abs(),
+            Shape::Chain(ref vertices) => extent(vertices.iter().map(|vertex| vertex.x)),
+            Shape::Compound(_) => self.hull_extent(&Vector2::new(1., 0.)),
         }
     }
 
     pub fn height(&self) -> f32 {
         match self.shape {
-            Shape::Rectangle { height, .. } => height.clone()
+            Shape::Rectangle { height, .. } => height.clone(),
+            Shape::Circle { radius } => radius * 2.,
+            Shape::ConvexPolygon(ref vertices) => extent(vertices.iter().map(|vertex| vertex.y)),
+            Shape::Capsule { half_height, radius } => (half_height + radius) * 2.,
+            Shape::Segment { start, end } => (end.y - start.y).abs(),
+            Shape::Chain(ref vertices) => extent(vertices.iter().map(|vertex| vertex.y)),
+            Shape::Compound(_) => self.hull_extent(&Vector2::new(0., 1.)),
         }
     }
+
+    fn hull_extent(&self, axis: &Vector2<f32>) -> f32 {
+        let hulls = self.shape.hulls(&Vector2::new(0., 0.), 0.);
+        let (min, max) = hulls.iter()
+            .map(|hull| hull.project(axis))
+            .fold((std::f32::INFINITY, std::f32::NEG_INFINITY),
+                  |(min, max), (hull_min, hull_max)| (min.min(hull_min), max.max(hull_max)));
+        if min > max { 0. } else { max - min }
+    }
+}
+
+fn extent<I: Iterator<Item = f32>>(values: I) -> f32 {
+    let (min, max) = values.fold((std::f32::INFINITY, std::f32::NEG_INFINITY),
+                                 |(min, max), value| (min.min(value), max.max(value)));
+    if min > max { 0. } else { max - min }
 }
 
 /// Enables Collisions between this entity and any other entity
@@ -71,22 +339,87 @@ impl Collider2D {
     }
 
     pub fn collision(&self, self_pos: &Vector2<f32>, other: &Collider2D, other_pos: &Vector2<f32>) -> Option<Vector2<f32>> {
-        let Shape::Rectangle { width, height } = self.shape;
-        let Shape::Rectangle { width: other_width, height: other_height} = other.shape;
+        self.contact(self_pos, other, other_pos).map(|contact| contact.point)
+    }
 
-        let coll_center = self_pos + self.offset;
-        let other_coll_center = other_pos + other.offset;
+    /// Like 'collision', but also reports the contact normal, penetration depth and contact points,
+    /// as well as which children of compound colliders and which segments of chains were hit.
+    pub fn contact(&self, self_pos: &Vector2<f32>, other: &Collider2D, other_pos: &Vector2<f32>) -> Option<ColliderContact> {
+        self.contacts(self_pos, other, other_pos).into_iter().next()
+    }
 
-        if let (Some(coll_x), Some(coll_y)) = (
-            overlap_center(coll_center[0], width, other_coll_center[0], other_width),
-            overlap_center(coll_center[1], height, other_coll_center[1], other_height)
-        ) {
-            Some(Vector2::new(coll_x, coll_y))
-        } else {
-            None
+    /// Every collision between the children of both colliders.
+    /// Colliders that aren't compound colliders count as a single child.
+    pub fn contacts(&self, self_pos: &Vector2<f32>, other: &Collider2D, other_pos: &Vector2<f32>) -> Vec<ColliderContact> {
+        let other_parts = other.parts();
+        let mut contacts = Vec::new();
+
+        for (child, shape, offset, rotation) in self.parts() {
+            for (other_child, other_shape, other_offset, other_rotation) in other_parts.iter() {
+                if let Some(mut contact) = shape_contact(shape, &(self_pos + offset), rotation,
+                                                         other_shape, &(other_pos + other_offset), *other_rotation) {
+                    contact.child = child;
+                    contact.other_child = *other_child;
+                    contacts.push(contact);
+                }
+            }
+        }
+
+        contacts
+    }
+
+    /// The shapes of all children with their offsets, rotations and indices.
+    /// Nested compound colliders share the index of their topmost parent.
+    fn parts(&self) -> Vec<(Option<usize>, &Shape, Vector2<f32>, f32)> {
+        match self.shape {
+            Shape::Compound(ref children) => children.iter()
+                .enumerate()
+                .flat_map(|(i, child)| child.parts().into_iter()
+                    .map(move |(_, shape, offset, rotation)| (Some(i), shape, offset, rotation)))
+                .map(|(i, shape, offset, rotation)| (
+                    i,
+                    shape,
+                    self.offset + rotate_vec(&offset, self.rotation),
+                    self.rotation + rotation,
+                ))
+                .collect(),
+            _ => vec![(None, &self.shape, self.offset, self.rotation)],
         }
     }
 
+    /// How far this collider can move along 'motion' until it touches 'other', as a fraction of 'motion',
+    /// together with the contact normal pointing towards 'other'.
+    /// None if they don't touch within 'motion' or already overlap.
+    pub fn time_of_impact(&self, self_pos: &Vector2<f32>, motion: &Vector2<f32>,
+                          other: &Collider2D, other_pos: &Vector2<f32>) -> Option<(f32, Vector2<f32>)> {
+        let other_hulls = other.hulls(other_pos);
+        self.hulls(self_pos).iter()
+            .flat_map(|hull| other_hulls.iter()
+                .filter_map(move |other_hull| geometry::time_of_impact(hull, motion, other_hull)))
+            .fold(None, |earliest: Option<(f32, Vector2<f32>)>, candidate| match earliest {
+                Some(earliest) if earliest.0 <= candidate.0 => Some(earliest),
+                _ => Some(candidate),
+            })
+    }
+
+    pub fn contains_point(&self, self_pos: &Vector2<f32>, point: &Vector2<f32>) -> bool {
+        self.hulls(self_pos).iter().any(|hull| hull.contains_point(point))
+    }
+
+    /// Distance along the ray to where it hits this collider and the surface normal there.
+    ///
+    /// 'direction' needs to be a unit vector. Parts of the collider containing 'origin' aren't hit.
+    pub fn raycast(&self, self_pos: &Vector2<f32>, origin: &Vector2<f32>, direction: &Vector2<f32>, max_distance: f32)
+        -> Option<(f32, Vector2<f32>)>
+    {
+        self.hulls(self_pos).iter()
+            .filter_map(|hull| hull.raycast(origin, direction, max_distance))
+            .fold(None, |nearest: Option<(f32, Vector2<f32>)>, hit| match nearest {
+                Some(nearest) if nearest.0 <= hit.0 => Some(nearest),
+                _ => Some(hit),
+            })
+    }
+
     pub fn collision_paths(&self, self_pos: &Vector2<f32>, other: &Collider2D, other_pos: &Vector2<f32>)
         -> Option<(Vector2<f32>, Vector2<f32>)>
     {
@@ -96,6 +429,89 @@ impl Collider2D {
     }
 }
 
+fn shape_contact(shape: &Shape, center: &Vector2<f32>, rotation: f32,
+                 other_shape: &Shape, other_center: &Vector2<f32>, other_rotation: f32) -> Option<ColliderContact> {
+    // Everything, including oriented boxes, is tested with the separating axis theorem
+    let mut contact = hull_contact(shape, center, rotation, other_shape, other_center, other_rotation)?;
+
+    // For axis aligned rectangles and circles, we know the exact center of the overlap
+    let exact_point = match (shape, other_shape) {
+        (&Shape::Rectangle { width, height },
+            &Shape::Rectangle { width: other_width, height: other_height })
+            if rotation == 0. && other_rotation == 0. =>
+            rect_overlap_center(*center, width, height,
+                                *other_center, other_width, other_height),
+        (&Shape::Circle { radius }, &Shape::Circle { radius: other_radius }) =>
+            circle_overlap_center(*center, radius, *other_center, other_radius),
+        (&Shape::Rectangle { width, height }, &Shape::Circle { radius }) if rotation == 0. =>
+            rect_circle_overlap_center(*center, width, height, *other_center, radius),
+        (&Shape::Circle { radius }, &Shape::Rectangle { width, height }) if other_rotation == 0. =>
+            rect_circle_overlap_center(*other_center, width, height, *center, radius),
+        _ => None,
+    };
+
+    if let Some(point) = exact_point {
+        contact.point = point;
+    }
+    Some(contact)
+}
+
+/// Tests every pair of hulls and keeps the deepest contact.
+fn hull_contact(shape: &Shape, center: &Vector2<f32>, rotation: f32,
+                other_shape: &Shape, other_center: &Vector2<f32>, other_rotation: f32) -> Option<ColliderContact> {
+    let is_chain = |shape: &Shape| match *shape {
+        Shape::Chain(_) => true,
+        _ => false,
+    };
+
+    let other_hulls = other_shape.hulls(other_center, other_rotation);
+    let mut deepest: Option<(f32, ColliderContact)> = None;
+    for (i, hull) in shape.hulls(center, rotation).iter().enumerate() {
+        for (j, other_hull) in other_hulls.iter().enumerate() {
+            let contact = match geometry::intersect(hull, other_hull) {
+                Some(contact) => contact,
+                None => continue,
+            };
+
+            if deepest.as_ref().map_or(true, |(depth, _)| contact.depth > *depth) {
+                deepest = Some((contact.depth, ColliderContact {
+                    point: contact.point,
+                    normal: contact.normal,
+                    depth: contact.depth,
+                    points: contact.points,
+                    segment: if is_chain(shape) { Some(i) } else { None },
+                    other_segment: if is_chain(other_shape) { Some(j) } else { None },
+                    child: None,
+                    other_child: None,
+                }));
+            }
+        }
+    }
+
+    deepest.map(|(_, contact)| contact)
+}
+
+/// Result of a collision test between two colliders.
+#[derive(Clone, Debug)]
+pub struct ColliderContact {
+    /// Center of the overlapping area.
+    pub point: Vector2<f32>,
+    /// Unit vector pointing from the first collider towards the second one.
+    pub normal: Vector2<f32>,
+    /// How far the colliders need to be moved apart along 'normal' to stop overlapping.
+    pub depth: f32,
+    /// Up to two points where the colliders touch.
+    pub points: Vec<Vector2<f32>>,
+    /// Index of the hit segment, if the first collider is a chain.
+    pub segment: Option<usize>,
+    /// Index of the hit segment, if the second collider is a chain.
+    pub other_segment: Option<usize>,
+    /// Index of the involved child, if the first collider is a compound collider.
+    pub child: Option<usize>,
+    /// Index of the involved child, if the second collider is a compound collider.
+    pub other_child: Option<usize>,
+}
+
 #[derive(Clone)]
 struct Overlap {
     pub start: f32,
@@ -149,6 +565,64 @@ fn overlap_center(pos: f32, extent: f32, other_pos: f32, other_extent: f32) -> O
     overlap.map(|ov| (ov.start + ov.width * 0.5))
 }
 
+fn rect_overlap_center(center: Vector2<f32>, width: f32, height: f32,
+                       other_center: Vector2<f32>, other_width: f32, other_height: f32) -> Option<Vector2<f32>> {
+    if let (Some(coll_x), Some(coll_y)) = (
+        overlap_center(center[0], width, other_center[0], other_width),
+        overlap_center(center[1], height, other_center[1], other_height)
+    ) {
+        Some(Vector2::new(coll_x, coll_y))
+    } else {
+        None
+    }
+}
+
+/// The center of the overlap lies on the line connecting both circle centers.
+fn circle_overlap_center(center: Vector2<f32>, radius: f32,
+                         other_center: Vector2<f32>, other_radius: f32) -> Option<Vector2<f32>> {
+    let between = other_center - center;
+    let distance = between.norm();
+
+    if distance > radius + other_radius {
+        return None
+    }
+    if distance == 0. {
+        return Some(center)
+    }
+
+    // Overlap on the connecting line, measured from 'center'
+    let start = (distance - other_radius).max(-radius);
+    let end = radius.min(distance + other_radius);
+
+    Some(center + between / distance * ((start + end) * 0.5))
+}
+
+fn rect_circle_overlap_center(rect_center: Vector2<f32>, width: f32, height: f32,
+                              circle_center: Vector2<f32>, radius: f32) -> Option<Vector2<f32>> {
+    let half_width = width.abs() * 0.5;
+    let half_height = height.abs() * 0.5;
+
+    // Point of the rectangle closest to the circle
+    let closest = Vector2::new(
+        circle_center[0].max(rect_center[0] - half_width).min(rect_center[0] + half_width),
+        circle_center[1].max(rect_center[1] - half_height).min(rect_center[1] + half_height),
+    );
+
+    let between = closest - circle_center;
+    let distance = between.norm();
+
+    if distance > radius {
+        return None
+    }
+    if distance == 0. {
+        // The circle center lies within the rectangle, use the circle's bounding box instead
+        return rect_overlap_center(rect_center, width, height, circle_center, radius * 2., radius * 2.)
+    }
+
+    let deepest = circle_center + between / distance * radius;
+    Some((closest + deepest) * 0.5)
+}
+
 impl Component for Collider2D {
     type Storage = DenseVecStorage<Self>;
 }
@@ -162,13 +636,69 @@ impl Component for DeactivateCollider {
     type Storage = NullStorage<Self>;
 }
 
+/// Turns the collider into a trigger area, e.g. a checkpoint or a kill zone.
+///
+/// Collisions with sensors are reported like any other collision, with 'is_sensor' set
+/// on the 'CollisionEvent', but they never get resolved by the 'HandleCollisionsSystem'.
+///
+/// This component has no effect if the host-entity does not have a ['Collider2D'] component as well.
+#[derive(Default)]
+pub struct Sensor;
+impl Component for Sensor {
+    type Storage = NullStorage<Self>;
+}
+
+/// Filters which entities collide with each other.
+///
+/// Two entities only collide if each one is a member of a group in the mask of the other one.
+/// Entities without this component are members of every group and collide with every group.
+///
+/// For example, bullets that should neither hit each other nor the player firing them:
+/// ```ignore
+/// const PLAYER: u32 = 1;
+/// const ENEMY: u32 = 1 << 1;
+/// const PLAYER_BULLET: u32 = 1 << 2;
+///
+/// let bullet = CollisionGroups::new(PLAYER_BULLET, ENEMY);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionGroups {
+    /// Groups this entity belongs to, one bit per group.
+    pub membership: u32,
+    /// Groups this entity collides with, one bit per group.
+    pub mask: u32,
+}
+
+impl CollisionGroups {
+    pub fn new(membership: u32, mask: u32) -> Self {
+        CollisionGroups {
+            membership,
+            mask,
+        }
+    }
+
+    pub fn can_collide_with(&self, other: &CollisionGroups) -> bool {
+        self.membership & other.mask != 0 && other.membership & self.mask != 0
+    }
+}
+
+impl Default for CollisionGroups {
+    fn default() -> Self {
+        CollisionGroups::new(!0, !0)
+    }
+}
+
+impl Component for CollisionGroups {
+    type Storage = DenseVecStorage<Self>;
+}
+
 /// Restricts collision testing for an entity.
 ///
 /// Entities with this component don't test for collisions themselves,
 /// rather they get tested by other, active colliders.
 ///
 /// That way, we can avoid testing for collisions between entities that can't ever collide anyway.
-/// For example, because they are both static and don't move.
+/// For example, because they are both static and don't move, like level geometry made of chains.
 ///
 /// This component has no effect if the host-entity does not have a ['Collider2D'] component as well.
 #[derive(Default)]
@@ -177,12 +707,274 @@ impl Component for PassiveCollider {
     type Storage = NullStorage<Self>;
 }
 
+/// Turns the collider of an entity into a platform that can be passed through in one direction,
+/// like platforms you can jump up through and land on.
+///
+/// Colliders coming from the side 'direction' points to land on the platform, everything else passes through.
+/// A contact that started out passing through keeps doing so until the colliders stop touching.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OneWayPlatform {
+    pub direction: Vector2<f32>,
+}
+
+impl OneWayPlatform {
+    pub fn new(direction: Vector2<f32>) -> Self {
+        OneWayPlatform {
+            direction,
+        }
+    }
+
+    /// Whether a collider touching the platform passes through it.
+    ///
+    /// 'normal' points from the platform towards the other collider and
+    /// 'relative_velocity' is the velocity of the other collider minus the one of the platform.
+    pub fn lets_pass(&self, normal: &Vector2<f32>, relative_velocity: &Vector2<f32>) -> bool {
+        normal.dot(&self.direction) <= 0. || relative_velocity.dot(&self.direction) > 0.
+    }
+}
+
+impl Component for OneWayPlatform {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Opts an entity into continuous collision detection.
+///
+/// Fast entities can pass through thin colliders within a single frame. The 'MovementSystem' stops
+/// entities with this component at the first collider they would hit along the way instead,
+/// so the collision gets reported and handled. Sensors neither use nor stop continuous collision detection.
+#[derive(Default)]
+pub struct ContinuousCollision;
+impl Component for ContinuousCollision {
+    type Storage = NullStorage<Self>;
+}
+
+/// Moves an entity like a player character: By a desired amount, sliding along the colliders in its way
+/// instead of bouncing off of them. Moved by the 'CharacterControllerSystem', which needs the entity
+/// to have a 'Collider2D' as well. Don't give it a 'Velocity', set 'desired_displacement' every frame instead.
+///
+/// After every move, the controller tells what the character touched.
+#[derive(Clone, Debug)]
+pub struct CharacterController2D {
+    /// How far the character should move this frame, in world space. Reset to zero after moving.
+    ///
+    /// With a fixed time step, the steps of a frame share it evenly, see 'PhysicsTime::frame_fraction',
+    /// and it's reset after them. Frames shorter than a step may run no step at all, in which case it's
+    /// kept for the next frame, so add to it instead of overwriting it.
+    pub desired_displacement: Vector2<f32>,
+    /// Unit vector pointing upwards, which tells floors from walls and ceilings.
+    pub up: Vector2<f32>,
+    /// Steepest slope in radians the character can stand on. Steeper ones are walls.
+    pub max_slope_angle: f32,
+    /// Height of obstacles the character steps onto while grounded, instead of being stopped by them.
+    pub step_height: f32,
+    /// The character stands on a floor.
+    pub grounded: bool,
+    /// Normal of the floor the character stands on, pointing away from the floor.
+    pub ground_normal: Option<Vector2<f32>>,
+    pub on_ceiling: bool,
+    pub on_wall: bool,
+    /// Every entity the character bumped into during the last move.
+    pub touched: Vec<Entity>,
+}
+
+impl CharacterController2D {
+    pub fn new(max_slope_angle: f32, step_height: f32) -> Self {
+        CharacterController2D {
+            desired_displacement: Vector2::new(0., 0.),
+            up: Vector2::new(0., 1.),
+            max_slope_angle,
+            step_height,
+            grounded: false,
+            ground_normal: None,
+            on_ceiling: false,
+            on_wall: false,
+            touched: Vec::new(),
+        }
+    }
+}
+
+impl Default for CharacterController2D {
+    fn default() -> Self {
+        CharacterController2D::new(std::f32::consts::PI * 0.25, 0.)
+    }
+}
+
+impl Component for CharacterController2D {
+    type Storage = DenseVecStorage<Self>;
+}
+
 /// Makes an entity move.
 pub struct Velocity(pub Vector2<f32>);
 impl Component for Velocity {
     type Storage = DenseVecStorage<Self>;
 }
 
+/// Resource: Acceleration applied to every entity with a 'Velocity' by the 'MovementSystem'.
+///
+/// Defaults to no gravity at all. Scale it for single entities with 'GravityScale'.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gravity(pub Vector2<f32>);
+
+impl Default for Gravity {
+    fn default() -> Self {
+        Gravity(Vector2::new(0., 0.))
+    }
+}
+
+/// Resource: How the physics systems advance in time.
+///
+/// With a fixed time step, see 'ColliderPhysicsBundle::with_fixed_timestep', the physics systems
+/// run zero or more times a frame, advancing by the same step every time. Whatever time is left
+/// gets carried over to the next frame. To render smoothly, interpolate between the 'PreviousTransform'
+/// and the current Transform of an entity by 'alpha', the fraction of a step that is left.
+#[derive(Clone, Debug)]
+pub struct PhysicsTime {
+    fixed_step: Option<f32>,
+    frame_steps: usize,
+    alpha: f32,
+}
+
+impl PhysicsTime {
+    /// The length of a step in seconds, None if the physics systems advance by the frame time.
+    pub fn fixed_step(&self) -> Option<f32> {
+        self.fixed_step
+    }
+
+    /// The time the physics systems advance by: The fixed step if there is one, the frame time otherwise.
+    pub fn delta_seconds(&self, time: &Time) -> f32 {
+        self.fixed_step.unwrap_or_else(|| time.delta_seconds())
+    }
+
+    /// How many steps run this frame with a fixed time step, 1 otherwise.
+    pub fn frame_steps(&self) -> usize {
+        self.frame_steps
+    }
+
+    /// The share of the frame a single physics update covers: One over the number of steps run this frame
+    /// if there is a fixed step, 1 otherwise. Amounts given per frame, like the desired displacement of a
+    /// 'CharacterController2D', are scaled by it for every step, so the steps of a frame add up to them.
+    pub fn frame_fraction(&self) -> f32 {
+        match self.fixed_step {
+            Some(_) if self.frame_steps > 0 => 1. / self.frame_steps as f32,
+            Some(_) => 0.,
+            None => 1.,
+        }
+    }
+
+    /// How far the time left over after the last step got into the next step, between 0 and 1.
+    /// Always 1 without a fixed time step.
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    pub(crate) fn set_fixed_step(&mut self, step: f32) {
+        self.fixed_step = Some(step);
+    }
+
+    pub(crate) fn set_frame_steps(&mut self, steps: usize) {
+        self.frame_steps = steps;
+    }
+
+    pub(crate) fn set_alpha(&mut self, alpha: f32) {
+        self.alpha = alpha;
+    }
+}
+
+impl Default for PhysicsTime {
+    fn default() -> Self {
+        PhysicsTime {
+            fixed_step: None,
+            frame_steps: 1,
+            alpha: 1.,
+        }
+    }
+}
+
+/// The Transform of an entity before the last physics step, for rendering smoothly with a fixed time step.
+///
+/// Only entities with this component keep track of it. Before every step, it's overwritten with the
+/// current Transform, see 'PhysicsTime::alpha' for interpolating between the two.
+/// Without a fixed time step, it isn't updated.
+#[derive(Clone, Debug, Default)]
+pub struct PreviousTransform(pub Transform);
+impl Component for PreviousTransform {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// How strongly 'Gravity' affects an entity. Entities without this component are affected fully,
+/// as if it was 1. Use 0 for entities that shouldn't fall.
+pub struct GravityScale(pub f32);
+impl Component for GravityScale {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Changes the 'Velocity' of an entity every second, e.g. a rocket's thrust.
+pub struct Acceleration(pub Vector2<f32>);
+impl Component for Acceleration {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Pushes an entity, accelerating it by the force divided by the mass of its 'RigidBody2D'.
+/// Entities without a 'RigidBody2D' have a mass of 1. Immovable bodies aren't accelerated.
+pub struct Force(pub Vector2<f32>);
+impl Component for Force {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Slows an entity down over time, like drag. The higher the value, the faster it slows down.
+pub struct Damping(pub f32);
+impl Component for Damping {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Limits the speed of an entity.
+pub struct MaxSpeed(pub f32);
+impl Component for MaxSpeed {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// Mass and material of an entity, used when its collisions are handled with 'HandleCollisionMode::Impulse'.
+///
+/// Entities without this component behave like 'RigidBody2D::default()'.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RigidBody2D {
+    /// A mass of zero or infinity makes the entity immovable.
+    pub mass: f32,
+    /// How much of the approaching speed is kept after a collision. 0 doesn't bounce at all, 1 is fully elastic.
+    /// The higher restitution of both collision partners is used.
+    pub restitution: f32,
+    /// Coulomb friction coefficient. The geometric mean of both collision partners is used.
+    pub friction: f32,
+}
+
+impl RigidBody2D {
+    pub fn new(mass: f32, restitution: f32, friction: f32) -> Self {
+        RigidBody2D {
+            mass,
+            restitution,
+            friction,
+        }
+    }
+
+    pub fn inverse_mass(&self) -> f32 {
+        if self.mass > 0. && self.mass.is_finite() {
+            1. / self.mass
+        } else {
+            0.
+        }
+    }
+}
+
+impl Default for RigidBody2D {
+    fn default() -> Self {
+        RigidBody2D::new(1., 0., 0.5)
+    }
+}
+
+impl Component for RigidBody2D {
+    type Storage = DenseVecStorage<Self>;
+}
+
 /// Automatically handle collisions. The way it is handled is directed by the variant.
 #[derive(Debug)]
 pub enum HandleCollisionMode {
@@ -195,6 +987,9 @@ pub enum HandleCollisionMode {
     Bounce(f32),
     /// Velocity points away from the collision partner
     Oppose,
+    /// Exchange momentum with the collision partner, based on the 'RigidBody2D' of both entities.
+    /// Partners that aren't handled with 'Impulse' as well, or are 'PassiveCollider's, are immovable.
+    Impulse,
 }
 impl Component for HandleCollisionMode {
     type Storage = DenseVecStorage<Self>;
@@ -208,7 +1003,8 @@ impl Default for HandleCollisionMode {
 
 #[cfg(test)]
 mod test_collision {
-    use crate::physics::components::overlap_center;
+    use crate::components::{overlap_center, Collider2D};
+    use amethyst::core::nalgebra::Vector2;
 
     #[test]
     fn test_overlap() {
@@ -227,4 +1023,215 @@ mod test_collision {
         // Second inside first
         assert_eq!(overlap_center(-124.2345, 3456.32, -2.34, 45.2).map(|pos| (pos * 100.).round() / 100.), Some(-2.34));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_circle_circle() {
+        let circle = Collider2D::circle_without_offset(2.);
+
+        // Apart
+        assert_eq!(Collider2D::circle_without_offset(1.).collision(&Vector2::new(0., 0.),
+                   &Collider2D::circle_without_offset(1.), &Vector2::new(3., 0.)), None);
+        // Overlapping
+        assert_eq!(circle.collision(&Vector2::new(0., 0.), &circle, &Vector2::new(3., 0.)),
+                   Some(Vector2::new(1.5, 0.)));
+        // Second inside first
+        assert_eq!(Collider2D::circle_without_offset(5.).collision(&Vector2::new(0., 0.),
+                   &Collider2D::circle_without_offset(1.), &Vector2::new(1., 0.)),
+                   Some(Vector2::new(1., 0.)));
+    }
+
+    #[test]
+    fn test_circle_rect() {
+        let rect = Collider2D::rect_without_offset(4., 4.);
+
+        // Side
+        assert_eq!(rect.collision(&Vector2::new(0., 0.),
+                   &Collider2D::circle_without_offset(2.), &Vector2::new(3., 0.)),
+                   Some(Vector2::new(1.5, 0.)));
+        // Bounding boxes overlap, but the circle misses the corner
+        assert_eq!(rect.collision(&Vector2::new(0., 0.),
+                   &Collider2D::circle_without_offset(1.), &Vector2::new(3., 3.)), None);
+        // Circle center inside the rectangle, order of arguments doesn't matter
+        assert_eq!(Collider2D::circle_without_offset(1.).collision(&Vector2::new(0.5, 0.),
+                   &rect, &Vector2::new(0., 0.)),
+                   Some(Vector2::new(0.5, 0.)));
+    }
+
+    #[test]
+    fn test_capsule() {
+        let capsule = Collider2D::capsule_without_offset(1., 0.5);
+
+        // Rectangle resting on top of the capsule
+        assert_eq!(capsule.collision(&Vector2::new(0., 0.),
+                   &Collider2D::rect_without_offset(2., 1.5), &Vector2::new(0., 2.)),
+                   Some(Vector2::new(0., 1.375)));
+        // Bounding boxes overlap, but the circle misses the rounded end
+        assert_eq!(capsule.collision(&Vector2::new(0., 0.),
+                   &Collider2D::circle_without_offset(0.2), &Vector2::new(0.6, 1.6)), None);
+        // Side
+        assert!(capsule.collides_with(&Vector2::new(0., 0.),
+                &Collider2D::circle_without_offset(0.2), &Vector2::new(0.6, 0.)));
+    }
+
+    #[test]
+    fn test_chain() {
+        let terrain = Collider2D::chain_without_offset(vec![
+            Vector2::new(-4., 0.),
+            Vector2::new(0., 0.),
+            Vector2::new(4., 2.),
+        ]);
+
+        let contact = Collider2D::circle_without_offset(1.)
+            .contact(&Vector2::new(-2., 0.5), &terrain, &Vector2::new(0., 0.)).unwrap();
+        assert_eq!(contact.segment, None);
+        assert_eq!(contact.other_segment, Some(0));
+
+        let contact = terrain
+            .contact(&Vector2::new(0., 0.), &Collider2D::circle_without_offset(1.), &Vector2::new(2., 1.5)).unwrap();
+        assert_eq!(contact.segment, Some(1));
+
+        // Between the segments, below the slope
+        assert!(!terrain.collides_with(&Vector2::new(0., 0.),
+                &Collider2D::circle_without_offset(0.5), &Vector2::new(3., -1.)));
+    }
+
+    #[test]
+    fn test_compound() {
+        // L-shape
+        let ship = Collider2D::compound_without_offset(vec![
+            Collider2D::rect(4., 1., Vector2::new(0., -1.5)),
+            Collider2D::rect(1., 4., Vector2::new(-1.5, 0.)),
+        ]);
+
+        let contacts = ship.contacts(&Vector2::new(0., 0.),
+                                     &Collider2D::circle_without_offset(0.5), &Vector2::new(1.5, -0.8));
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].child, Some(0));
+        assert_eq!(contacts[0].other_child, None);
+
+        // Inside the corner of the L
+        assert_eq!(ship.contacts(&Vector2::new(0., 0.),
+                   &Collider2D::rect_without_offset(2., 2.), &Vector2::new(-0.5, -0.5)).len(), 2);
+        // Inside the bounding box, but outside the L
+        assert!(!ship.collides_with(&Vector2::new(0., 0.),
+                &Collider2D::circle_without_offset(0.5), &Vector2::new(1., 1.)));
+    }
+
+    #[test]
+    fn test_rotation() {
+        let bar = Collider2D::rect_without_offset(4., 0.5);
+        let upright = bar.rotated_by(std::f32::consts::PI * 0.5);
+        let ball = Collider2D::circle_without_offset(0.2);
+
+        assert!(!bar.collides_with(&Vector2::new(0., 0.), &ball, &Vector2::new(0., 1.5)));
+        assert!(upright.collides_with(&Vector2::new(0., 0.), &ball, &Vector2::new(0., 1.5)));
+
+        assert!(bar.collides_with(&Vector2::new(0., 0.), &ball, &Vector2::new(1.5, 0.)));
+        assert!(!upright.collides_with(&Vector2::new(0., 0.), &ball, &Vector2::new(1.5, 0.)));
+
+        // Offsets rotate around the entity
+        let arm = Collider2D::rect(1., 1., Vector2::new(2., 0.)).rotated_by(std::f32::consts::PI);
+        assert!(arm.collides_with(&Vector2::new(0., 0.), &ball, &Vector2::new(-2., 0.)));
+    }
+
+    #[test]
+    fn test_transformed_by() {
+        use amethyst::core::transform::Transform;
+
+        // A flipped sprite mirrors the offset of its collider
+        let mut flipped = Transform::default();
+        flipped.set_scale(-1., 1., 1.);
+        flipped.translate_y(5.);
+        let (arm, position) = Collider2D::rect(2., 1., Vector2::new(1., 0.)).transformed_by(&flipped);
+        let aabb = arm.aabb(&position);
+        assert!((aabb.min - Vector2::new(-2., 4.5)).norm() < 1e-4);
+        assert!((aabb.max - Vector2::new(0., 5.5)).norm() < 1e-4);
+
+        // Scaling a rotated transform stretches the collider along the transform's axes
+        let mut turned = Transform::default();
+        turned.set_rotation_euler(0., 0., std::f32::consts::PI * 0.5);
+        turned.set_scale(3., 1., 1.);
+        let (bar, position) = Collider2D::rect_without_offset(1., 1.).transformed_by(&turned);
+        let aabb = bar.aabb(&position);
+        assert!((aabb.min - Vector2::new(-0.5, -1.5)).norm() < 1e-4);
+        assert!((aabb.max - Vector2::new(0.5, 1.5)).norm() < 1e-4);
+    }
+
+    #[test]
+    fn test_collision_groups() {
+        use crate::components::CollisionGroups;
+
+        let player = CollisionGroups::new(0b001, 0b110);
+        let enemy = CollisionGroups::new(0b010, 0b101);
+        let player_bullet = CollisionGroups::new(0b100, 0b010);
+
+        assert!(player.can_collide_with(&enemy));
+        assert!(player_bullet.can_collide_with(&enemy));
+        assert!(!player_bullet.can_collide_with(&player));
+        assert!(!enemy.can_collide_with(&enemy));
+        assert!(CollisionGroups::default().can_collide_with(&enemy));
+    }
+
+    #[test]
+    fn test_time_of_impact() {
+        let bullet = Collider2D::circle_without_offset(1.);
+        let wall = Collider2D::rect_without_offset(16., 200.);
+        let motion = Vector2::new(3000. / 60., 0.);
+
+        // The bullet would end up behind the wall
+        assert!(!bullet.collides_with(&motion, &wall, &Vector2::new(20., 0.)));
+
+        let (time, normal) = bullet.time_of_impact(&Vector2::new(0., 0.), &motion,
+                                                   &wall, &Vector2::new(20., 0.)).unwrap();
+        assert!((motion[0] * time - 11.).abs() < 0.1);
+        assert_eq!(normal, Vector2::new(1., 0.));
+
+        assert!(bullet.time_of_impact(&Vector2::new(0., 0.), &-motion, &wall, &Vector2::new(20., 0.)).is_none());
+    }
+
+    #[test]
+    fn test_one_way_platform() {
+        use crate::components::OneWayPlatform;
+
+        let platform = OneWayPlatform::new(Vector2::new(0., 1.));
+
+        // Lands from above
+        assert!(!platform.lets_pass(&Vector2::new(0., 1.), &Vector2::new(2., -5.)));
+        // Resting on top
+        assert!(!platform.lets_pass(&Vector2::new(0., 1.), &Vector2::new(2., 0.)));
+        // Jumps up from below
+        assert!(platform.lets_pass(&Vector2::new(0., -1.), &Vector2::new(0., 5.)));
+        // Overlaps it from above while moving up
+        assert!(platform.lets_pass(&Vector2::new(0., 1.), &Vector2::new(0., 5.)));
+        // Walks into it from the side
+        assert!(platform.lets_pass(&Vector2::new(1., 0.), &Vector2::new(-2., 0.)));
+    }
+
+    #[test]
+    fn test_raycast() {
+        let origin = Vector2::new(0., 0.);
+        let right = Vector2::new(1., 0.);
+        let distance = |collider: Collider2D, position: Vector2<f32>|
+            collider.raycast(&position, &origin, &right, 100.).map(|(distance, _)| (distance * 1000.).round() / 1000.);
+
+        assert_eq!(distance(Collider2D::rect_without_offset(2., 2.), Vector2::new(10., 0.)), Some(9.));
+        assert_eq!(distance(Collider2D::rect(2., 2., Vector2::new(0., 5.)), Vector2::new(10., 0.)), None);
+        assert_eq!(distance(Collider2D::circle_without_offset(1.), Vector2::new(10., 0.)), Some(9.));
+        assert_eq!(distance(Collider2D::capsule_without_offset(2., 1.), Vector2::new(10., 0.)), Some(9.));
+        assert_eq!(distance(Collider2D::convex_polygon_without_offset(vec![
+            Vector2::new(0., -1.), Vector2::new(1., 0.), Vector2::new(0., 1.), Vector2::new(-1., 0.),
+        ]), Vector2::new(10., 0.)), Some(9.));
+        assert_eq!(distance(Collider2D::segment_without_offset(Vector2::new(0., -1.), Vector2::new(0., 1.)), Vector2::new(10., 0.)),
+                   Some(10.));
+        assert_eq!(distance(Collider2D::chain_without_offset(vec![
+            Vector2::new(20., -1.), Vector2::new(10., -1.), Vector2::new(10., 1.), Vector2::new(20., 1.),
+        ]), Vector2::new(0., 0.)), Some(10.));
+        assert_eq!(distance(Collider2D::compound_without_offset(vec![
+            Collider2D::circle(1., Vector2::new(5., 0.)),
+            Collider2D::rect(2., 2., Vector2::new(-5., 0.)),
+        ]), Vector2::new(10., 0.)), Some(4.));
+        // Rotated by 90 degrees, the bar lies across the ray
+        assert_eq!(distance(Collider2D::rect_without_offset(0.5, 4.).rotated_by(std::f32::consts::PI * 0.5),
+                            Vector2::new(10., 0.)), Some(8.));
+    }
+}
@@ -18,10 +18,14 @@ use std::ops::Deref;
 
 /// Every Collision originates from an entity and has an associated path that goes from that
 /// Entity in the direction of the collision. Namely, 'path' points to the center of the overlapping area.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Collision {
     pub entity: Entity,
     pub path: Vector2<f32>,
+    /// The index of the hit segment, if the entity's collider is a chain.
+    pub segment: Option<usize>,
+    /// The index of the involved child, if the entity's collider is a compound collider.
+    pub child: Option<usize>,
 }
 
 impl Collision {
@@ -29,24 +33,73 @@ impl Collision {
         Self {
             entity,
             path,
+            segment: None,
+            child: None,
         }
     }
 }
 
 
+/// Describes how two colliders touch.
+#[derive(Debug, Clone)]
+pub struct ContactManifold {
+    /// Unit vector pointing from the first entity of the event towards the second one.
+    pub normal: Vector2<f32>,
+    /// How far the entities need to be moved apart along 'normal' to stop overlapping.
+    pub depth: f32,
+    /// Up to two points in world space where the colliders touch.
+    pub points: Vec<Vector2<f32>>,
+}
+
+/// Whether a contact between two colliders just started, went on since the last frame or ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionKind {
+    Started,
+    Persisting,
+    /// The colliders stopped touching. The event carries the data of the last frame they touched.
+    /// Either entity might have been deleted in the meantime.
+    Ended,
+}
+
+/// Identifies a contact across frames: Both entities and their involved children.
+pub type ContactKey = (Entity, Option<usize>, Entity, Option<usize>);
+
 /// On every registered collision, a CollisionEvent is sent to the corresponding EventChannel
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CollisionEvent {
     pub collisions: [Collision; 2],
+    pub manifold: ContactManifold,
+    pub kind: CollisionKind,
+    /// At least one of the entities is a 'Sensor', so the collision doesn't get resolved.
+    pub is_sensor: bool,
 }
 
 impl CollisionEvent {
-    pub fn new(first: Entity, second: Entity, collision_path_first: Vector2<f32>, collision_path_second: Vector2<f32>) -> Self {
+    pub fn new(first: Entity, second: Entity, collision_path_first: Vector2<f32>, collision_path_second: Vector2<f32>,
+               manifold: ContactManifold) -> Self {
         Self {
             collisions: [
                 Collision::new(first, collision_path_first),
                 Collision::new(second, collision_path_second),
-            ]
+            ],
+            manifold,
+            kind: CollisionKind::Started,
+            is_sensor: false,
+        }
+    }
+
+    /// Identifies the contact across frames: The entities and children involved.
+    pub fn contact_key(&self) -> ContactKey {
+        (self.collisions[0].entity, self.collisions[0].child,
+         self.collisions[1].entity, self.collisions[1].child)
+    }
+
+    /// Unit vector pointing from the entity at 'index' of 'collisions' towards its collision partner.
+    pub fn normal(&self, index: usize) -> Vector2<f32> {
+        if index == 0 {
+            self.manifold.normal
+        } else {
+            -self.manifold.normal
         }
     }
 
@@ -56,25 +109,50 @@ impl CollisionEvent {
                       first_collider: &Collider2D, second_collider: &Collider2D,
                       first_transform: &Transform, second_transform: &Transform) -> Option<Self>
     {
-        let first_scale = first_transform.scale();
-        let first_collider = first_collider.scaled_by(first_scale[0], first_scale[1]);
-
-        let second_scale = second_transform.scale();
-        let second_collider = second_collider.scaled_by(second_scale[0], second_scale[1]);
+        CollisionEvent::from_collisions(first, second,
+                                        first_collider, second_collider,
+                                        first_transform, second_transform)
+            .into_iter().next()
+    }
 
-        let translation = first_transform.translation();
-        let pos = Vector2::new(translation[0], translation[1]);
+    /// Generate a CollisionEvent for every pair of colliding children of both entities.
+    /// Colliders that aren't compound colliders count as a single child.
+    pub fn from_collisions(first: Entity, second: Entity,
+                           first_collider: &Collider2D, second_collider: &Collider2D,
+                           first_transform: &Transform, second_transform: &Transform) -> Vec<Self>
+    {
+        let (first_collider, pos) = first_collider.transformed_by(first_transform);
+        let (second_collider, other_pos) = second_collider.transformed_by(second_transform);
 
-        let other_translation = second_transform.translation();
-        let other_pos = Vector2::new(other_translation[0], other_translation[1]);
+        CollisionEvent::from_world_collisions(first, second,
+                                              &first_collider, &pos,
+                                              &second_collider, &other_pos)
+    }
 
-        if let Some(coll_paths) =
-        Collider2D::collision_paths(&first_collider, &pos,
-                                    &second_collider, &other_pos) {
-            Some(CollisionEvent::new(first, second, coll_paths.0, coll_paths.1))
-        } else {
-            None
-        }
+    /// Generate a CollisionEvent for every pair of colliding children of both entities.
+    /// The colliders need to be in world space already, see ['Collider2D::transformed_by_matrix'].
+    pub fn from_world_collisions(first: Entity, second: Entity,
+                                 first_collider: &Collider2D, first_pos: &Vector2<f32>,
+                                 second_collider: &Collider2D, second_pos: &Vector2<f32>) -> Vec<Self>
+    {
+        Collider2D::contacts(first_collider, first_pos, second_collider, second_pos)
+            .into_iter()
+            .map(|contact| {
+                let manifold = ContactManifold {
+                    normal: contact.normal,
+                    depth: contact.depth,
+                    points: contact.points,
+                };
+                let mut event = CollisionEvent::new(first, second,
+                                                    contact.point - first_pos, contact.point - second_pos,
+                                                    manifold);
+                event.collisions[0].segment = contact.segment;
+                event.collisions[0].child = contact.child;
+                event.collisions[1].segment = contact.other_segment;
+                event.collisions[1].child = contact.other_child;
+                event
+            })
+            .collect()
     }
 
     /// Generate a CollisionEvent from two entites. The relevant components are pulled from
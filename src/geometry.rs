@@ -0,0 +1,599 @@
+use amethyst::core::nalgebra::Vector2;
+
+const EPSILON: f32 = 1e-5;
+
+/// Hulls closer than this count as touching when looking for the time of impact.
+pub const TOI_TOLERANCE: f32 = 0.01;
+const MAX_ADVANCEMENT_STEPS: usize = 32;
+
+/// A convex shape in world space: The convex hull of 'vertices', inflated by 'radius'.
+///
+/// A circle is a single vertex with a radius, polygons don't have a radius.
+/// Vertices need to be ordered, either clockwise or counter-clockwise.
+#[derive(Clone, Debug)]
+pub struct Hull {
+    pub vertices: Vec<Vector2<f32>>,
+    pub radius: f32,
+}
+
+/// Result of a successful intersection test between two hulls.
+#[derive(Clone, Debug)]
+pub struct Contact {
+    /// Unit vector pointing from the first hull towards the second one.
+    pub normal: Vector2<f32>,
+    /// How far the hulls need to be moved apart along 'normal' to stop overlapping.
+    pub depth: f32,
+    /// Center of the overlapping area.
+    pub point: Vector2<f32>,
+    /// Where the hulls touch, halfway through the penetration.
+    /// Two points if the hulls touch along an edge, a single point otherwise.
+    pub points: Vec<Vector2<f32>>,
+}
+
+/// Axis aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Vector2<f32>, max: Vector2<f32>) -> Self {
+        Aabb {
+            min,
+            max,
+        }
+    }
+
+    /// Touching boxes count as overlapping.
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min[0] <= other.max[0] && other.min[0] <= self.max[0]
+            && self.min[1] <= other.max[1] && other.min[1] <= self.max[1]
+    }
+
+    pub fn contains(&self, other: &Aabb) -> bool {
+        self.min[0] <= other.min[0] && self.min[1] <= other.min[1]
+            && other.max[0] <= self.max[0] && other.max[1] <= self.max[1]
+    }
+
+    /// Grow the box by 'margin' in every direction.
+    pub fn expanded(&self, margin: f32) -> Aabb {
+        Aabb::new(self.min - Vector2::new(margin, margin), self.max + Vector2::new(margin, margin))
+    }
+
+    pub fn perimeter(&self) -> f32 {
+        2. * ((self.max[0] - self.min[0]) + (self.max[1] - self.min[1]))
+    }
+
+    /// The smallest box containing both boxes.
+    pub fn merged(&self, other: &Aabb) -> Aabb {
+        Aabb::new(
+            Vector2::new(self.min[0].min(other.min[0]), self.min[1].min(other.min[1])),
+            Vector2::new(self.max[0].max(other.max[0]), self.max[1].max(other.max[1])),
+        )
+    }
+}
+
+impl Hull {
+    pub fn new(vertices: Vec<Vector2<f32>>, radius: f32) -> Self {
+        Hull {
+            vertices,
+            radius,
+        }
+    }
+
+    pub fn translated(&self, offset: &Vector2<f32>) -> Hull {
+        Hull::new(self.vertices.iter().map(|vertex| vertex + offset).collect(), self.radius)
+    }
+
+    /// Lowest and highest value of the hull projected onto 'axis'.
+    pub fn project(&self, axis: &Vector2<f32>) -> (f32, f32) {
+        let (min, max) = self.project_core(axis);
+        (min - self.radius, max + self.radius)
+    }
+
+    fn project_core(&self, axis: &Vector2<f32>) -> (f32, f32) {
+        self.vertices.iter()
+            .map(|vertex| vertex.dot(axis))
+            .fold((std::f32::INFINITY, std::f32::NEG_INFINITY),
+                  |(min, max), proj| (min.min(proj), max.max(proj)))
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        let (min_x, max_x) = self.project(&Vector2::new(1., 0.));
+        let (min_y, max_y) = self.project(&Vector2::new(0., 1.));
+        Aabb::new(Vector2::new(min_x, min_y), Vector2::new(max_x, max_y))
+    }
+
+    /// The edges of the hull. A single vertex has no edges, two vertices form exactly one.
+    pub fn edges(&self) -> Vec<(Vector2<f32>, Vector2<f32>)> {
+        let count = self.vertices.len();
+        match count {
+            0 | 1 => Vec::new(),
+            2 => vec![(self.vertices[0], self.vertices[1])],
+            _ => (0..count)
+                .map(|i| (self.vertices[i], self.vertices[(i + 1) % count]))
+                .collect(),
+        }
+    }
+
+    fn edge_normals(&self) -> Vec<Vector2<f32>> {
+        self.edges().into_iter()
+            .filter_map(|(start, end)| {
+                let edge = end - start;
+                let length = edge.norm();
+                if length > EPSILON {
+                    Some(perpendicular(&edge) / length)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Whether 'point' lies within the hull without its radius.
+    fn core_contains(&self, point: &Vector2<f32>) -> bool {
+        if self.vertices.len() < 3 {
+            return false
+        }
+
+        let mut sign = 0f32;
+        for (start, end) in self.edges() {
+            let cross = cross(&(end - start), &(point - start));
+            if cross.abs() <= EPSILON {
+                continue
+            }
+            if sign == 0. {
+                sign = cross.signum();
+            } else if sign != cross.signum() {
+                return false
+            }
+        }
+        true
+    }
+
+    /// Point of the hull, without its radius, closest to 'point'.
+    pub fn closest_core_point(&self, point: &Vector2<f32>) -> Vector2<f32> {
+        if self.vertices.len() == 1 {
+            return self.vertices[0]
+        }
+        if self.core_contains(point) {
+            return *point
+        }
+
+        self.edges().into_iter()
+            .map(|(start, end)| closest_on_segment(point, &start, &end))
+            .fold(None, |closest: Option<Vector2<f32>>, candidate| match closest {
+                Some(closest) if (closest - point).norm() <= (candidate - point).norm() => Some(closest),
+                _ => Some(candidate),
+            })
+            .unwrap_or(*point)
+    }
+
+    /// Whether 'point' lies within the hull, including its radius.
+    pub fn contains_point(&self, point: &Vector2<f32>) -> bool {
+        (self.closest_core_point(point) - point).norm() <= self.radius
+    }
+
+    /// Distance along the ray to where it enters the hull and the surface normal there.
+    ///
+    /// 'direction' needs to be a unit vector. Rays starting within the hull don't hit it.
+    pub fn raycast(&self, origin: &Vector2<f32>, direction: &Vector2<f32>, max_distance: f32)
+        -> Option<(f32, Vector2<f32>)>
+    {
+        if self.contains_point(origin) {
+            return None
+        }
+
+        // The surface consists of the edges, moved outwards by the radius, and a circle around every vertex.
+        // Hitting any of those, or ones lying within the hull, first means the ray entered the hull there.
+        let mut hits = Vec::new();
+        for (start, end) in self.edges() {
+            let edge = end - start;
+            let length = edge.norm();
+            if length <= EPSILON {
+                continue
+            }
+
+            let normal = perpendicular(&edge) / length;
+            let offsets = if self.radius > 0. {
+                vec![normal * self.radius, -normal * self.radius]
+            } else {
+                vec![Vector2::new(0., 0.)]
+            };
+            for offset in offsets {
+                if let Some(distance) = ray_segment(origin, direction, &(start + offset), &(end + offset)) {
+                    let facing = if normal.dot(direction) > 0. { -normal } else { normal };
+                    hits.push((distance, facing));
+                }
+            }
+        }
+        if self.radius > 0. {
+            for vertex in &self.vertices {
+                if let Some(distance) = ray_circle(origin, direction, vertex, self.radius) {
+                    hits.push((distance, (origin + direction * distance - vertex) / self.radius));
+                }
+            }
+        }
+
+        hits.into_iter()
+            .filter(|(distance, _)| *distance <= max_distance)
+            .fold(None, |nearest: Option<(f32, Vector2<f32>)>, hit| match nearest {
+                Some(nearest) if nearest.0 <= hit.0 => Some(nearest),
+                _ => Some(hit),
+            })
+    }
+
+    /// Vertices of the hull that lie furthest along 'direction'.
+    /// Returns both ends of an edge if it's perpendicular to 'direction'.
+    fn support_feature(&self, direction: &Vector2<f32>) -> (Vector2<f32>, Vector2<f32>) {
+        let (_, max) = self.project_core(direction);
+        let mut feature = self.vertices.iter()
+            .filter(|vertex| vertex.dot(direction) >= max - EPSILON);
+
+        let first = *feature.next().expect("Hull without vertices");
+        let tangent = perpendicular(direction);
+        feature.fold((first, first), |(low, high), vertex| {
+            if vertex.dot(&tangent) < low.dot(&tangent) {
+                (*vertex, high)
+            } else if vertex.dot(&tangent) > high.dot(&tangent) {
+                (low, *vertex)
+            } else {
+                (low, high)
+            }
+        })
+    }
+}
+
+/// Closest points between the cores of both hulls or None if the cores intersect.
+fn closest_core_points(first: &Hull, second: &Hull) -> Option<(Vector2<f32>, Vector2<f32>)> {
+    for (start, end) in first.edges() {
+        for (other_start, other_end) in second.edges() {
+            if segments_intersect(&start, &end, &other_start, &other_end) {
+                return None
+            }
+        }
+    }
+    if first.vertices.iter().any(|vertex| second.core_contains(vertex))
+        || second.vertices.iter().any(|vertex| first.core_contains(vertex)) {
+        return None
+    }
+
+    // When two convex shapes don't intersect, at least one of the closest points is a vertex.
+    let from_first = first.vertices.iter()
+        .map(|vertex| (*vertex, second.closest_core_point(vertex)));
+    let from_second = second.vertices.iter()
+        .map(|vertex| (first.closest_core_point(vertex), *vertex));
+
+    from_first.chain(from_second)
+        .fold(None, |closest: Option<(Vector2<f32>, Vector2<f32>)>, candidate| match closest {
+            Some(closest) if (closest.1 - closest.0).norm() <= (candidate.1 - candidate.0).norm() => Some(closest),
+            _ => Some(candidate),
+        })
+}
+
+/// Tests two hulls for intersection using the separating axis theorem.
+///
+/// The tested axes are the edge normals of both hulls and, if the cores don't intersect,
+/// the axis between the closest points of the cores, which is needed for rounded hulls.
+pub fn intersect(first: &Hull, second: &Hull) -> Option<Contact> {
+    let mut axes = first.edge_normals();
+    axes.append(&mut second.edge_normals());
+
+    if let Some((on_first, on_second)) = closest_core_points(first, second) {
+        let between = on_second - on_first;
+        let distance = between.norm();
+        if distance > first.radius + second.radius {
+            return None
+        }
+        if distance > EPSILON {
+            axes.push(between / distance);
+        }
+    }
+    if axes.is_empty() {
+        // Both hulls are points at the same position
+        axes.push(Vector2::new(0., 1.));
+    }
+
+    let mut best: Option<(Vector2<f32>, f32)> = None;
+    for axis in axes {
+        let (min, max) = first.project(&axis);
+        let (other_min, other_max) = second.project(&axis);
+
+        // Distance 'second' needs to be pushed along or against 'axis' respectively
+        let forward = max - other_min;
+        let backward = other_max - min;
+        if forward < 0. || backward < 0. {
+            return None
+        }
+
+        let (normal, depth) = if forward <= backward { (axis, forward) } else { (-axis, backward) };
+        if best.map_or(true, |(_, best_depth)| depth < best_depth) {
+            best = Some((normal, depth));
+        }
+    }
+
+    best.map(|(normal, depth)| {
+        let (point, points) = contact_points(first, second, &normal);
+        Contact {
+            normal,
+            depth,
+            point,
+            points,
+        }
+    })
+}
+
+/// Distance between two hulls and the unit vector pointing from the first one towards the second one.
+///
+/// The distance is negative if only the radii of the hulls overlap and None is returned if their cores intersect.
+pub fn separation(first: &Hull, second: &Hull) -> Option<(f32, Vector2<f32>)> {
+    let (on_first, on_second) = closest_core_points(first, second)?;
+    let between = on_second - on_first;
+    let distance = between.norm();
+    if distance <= EPSILON {
+        return None
+    }
+
+    Some((distance - first.radius - second.radius, between / distance))
+}
+
+/// How far 'first' can move along 'motion' until it touches 'second', as a fraction of 'motion',
+/// together with the contact normal pointing from 'first' towards 'second'.
+///
+/// Uses conservative advancement: The hull is moved by its distance to 'second' divided by the speed it
+/// approaches 'second' with, which never overshoots. Returns None if the hulls don't touch within 'motion'
+/// or already overlap.
+pub fn time_of_impact(first: &Hull, motion: &Vector2<f32>, second: &Hull) -> Option<(f32, Vector2<f32>)> {
+    let mut time = 0.;
+    for _ in 0..MAX_ADVANCEMENT_STEPS {
+        let (distance, normal) = separation(&first.translated(&(motion * time)), second)?;
+        if distance < 0. && time == 0. {
+            return None
+        }
+
+        let approach = motion.dot(&normal);
+        if approach <= EPSILON {
+            return None
+        }
+        if distance <= TOI_TOLERANCE {
+            return Some((time, normal))
+        }
+
+        time += distance / approach;
+        if time > 1. {
+            return None
+        }
+    }
+
+    // Grazing motions converge slowly, but never overshoot
+    separation(&first.translated(&(motion * time)), second).map(|(_, normal)| (time, normal))
+}
+
+/// Approximates the center of the overlap between two intersecting hulls and the points they touch at.
+///
+/// Along the normal, all of them lie in the middle of the penetration.
+/// Along the tangent, the contact points span the part where the touching features
+/// of both hulls overlap and the center lies in the middle of them.
+fn contact_points(first: &Hull, second: &Hull, normal: &Vector2<f32>) -> (Vector2<f32>, Vec<Vector2<f32>>) {
+    let tangent = perpendicular(normal);
+
+    let (_, first_max) = first.project(normal);
+    let (second_min, _) = second.project(normal);
+    let normal_coord = (first_max + second_min) * 0.5;
+
+    let (start, end) = first.support_feature(normal);
+    let (other_start, other_end) = second.support_feature(&-*normal);
+    let (low, high) = (start.dot(&tangent).min(end.dot(&tangent)),
+                       start.dot(&tangent).max(end.dot(&tangent)));
+    let (other_low, other_high) = (other_start.dot(&tangent).min(other_end.dot(&tangent)),
+                                   other_start.dot(&tangent).max(other_end.dot(&tangent)));
+
+    let at = |tangent_coord: f32| normal * normal_coord + tangent * tangent_coord;
+
+    let (overlap_low, overlap_high) = (low.max(other_low), high.min(other_high));
+    if overlap_low > overlap_high {
+        let center = at((low + high + other_low + other_high) * 0.25);
+        return (center, vec![center])
+    }
+
+    let center = at((overlap_low + overlap_high) * 0.5);
+    if overlap_high - overlap_low > EPSILON {
+        (center, vec![at(overlap_low), at(overlap_high)])
+    } else {
+        (center, vec![center])
+    }
+}
+
+fn perpendicular(vec: &Vector2<f32>) -> Vector2<f32> {
+    Vector2::new(-vec[1], vec[0])
+}
+
+fn cross(first: &Vector2<f32>, second: &Vector2<f32>) -> f32 {
+    first[0] * second[1] - first[1] * second[0]
+}
+
+pub fn closest_on_segment(point: &Vector2<f32>, start: &Vector2<f32>, end: &Vector2<f32>) -> Vector2<f32> {
+    let segment = end - start;
+    let length_squared = segment.dot(&segment);
+    if length_squared <= EPSILON * EPSILON {
+        return *start
+    }
+
+    let t = ((point - start).dot(&segment) / length_squared).max(0.).min(1.);
+    start + segment * t
+}
+
+/// Distance along the ray to where it crosses the segment.
+fn ray_segment(origin: &Vector2<f32>, direction: &Vector2<f32>,
+               start: &Vector2<f32>, end: &Vector2<f32>) -> Option<f32> {
+    let edge = end - start;
+    let denominator = cross(direction, &edge);
+    if denominator.abs() <= EPSILON {
+        return None
+    }
+
+    let to_start = start - origin;
+    let distance = cross(&to_start, &edge) / denominator;
+    let along_edge = cross(&to_start, direction) / denominator;
+    if distance >= 0. && along_edge >= 0. && along_edge <= 1. {
+        Some(distance)
+    } else {
+        None
+    }
+}
+
+/// Distance along the ray to where it enters the circle. None if it starts within the circle.
+fn ray_circle(origin: &Vector2<f32>, direction: &Vector2<f32>, center: &Vector2<f32>, radius: f32) -> Option<f32> {
+    let from_center = origin - center;
+    let along = from_center.dot(direction);
+    let outside = from_center.dot(&from_center) - radius * radius;
+    if outside > 0. && along > 0. {
+        return None
+    }
+
+    let discriminant = along * along - outside;
+    if discriminant < 0. {
+        return None
+    }
+    let distance = -along - discriminant.sqrt();
+    if distance >= 0. { Some(distance) } else { None }
+}
+
+fn segments_intersect(start: &Vector2<f32>, end: &Vector2<f32>,
+                      other_start: &Vector2<f32>, other_end: &Vector2<f32>) -> bool {
+    let segment = end - start;
+    let other_segment = other_end - other_start;
+
+    let d1 = cross(&other_segment, &(start - other_start));
+    let d2 = cross(&other_segment, &(end - other_start));
+    let d3 = cross(&segment, &(other_start - start));
+    let d4 = cross(&segment, &(other_end - start));
+
+    if ((d1 > 0. && d2 < 0.) || (d1 < 0. && d2 > 0.))
+        && ((d3 > 0. && d4 < 0.) || (d3 < 0. && d4 > 0.)) {
+        return true
+    }
+
+    // Touching or collinear segments
+    (closest_on_segment(start, other_start, other_end) - start).norm() <= EPSILON
+        || (closest_on_segment(end, other_start, other_end) - end).norm() <= EPSILON
+        || (closest_on_segment(other_start, start, end) - other_start).norm() <= EPSILON
+        || (closest_on_segment(other_end, start, end) - other_end).norm() <= EPSILON
+}
+
+#[cfg(test)]
+mod test_geometry {
+    use crate::geometry::{intersect, time_of_impact, Hull, TOI_TOLERANCE};
+    use amethyst::core::nalgebra::Vector2;
+
+    fn square(x: f32, y: f32, size: f32) -> Hull {
+        let half = size * 0.5;
+        Hull::new(vec![
+            Vector2::new(x - half, y - half),
+            Vector2::new(x + half, y - half),
+            Vector2::new(x + half, y + half),
+            Vector2::new(x - half, y + half),
+        ], 0.)
+    }
+
+    #[test]
+    fn test_polygons() {
+        // Apart
+        assert!(intersect(&square(0., 0., 2.), &square(3., 0., 2.)).is_none());
+
+        let contact = intersect(&square(0., 0., 2.), &square(1.5, 0., 2.)).unwrap();
+        assert_eq!(contact.normal, Vector2::new(1., 0.));
+        assert_eq!(contact.depth, 0.5);
+        assert_eq!(contact.point, Vector2::new(0.75, 0.));
+        assert_eq!(contact.points, vec![Vector2::new(0.75, -1.), Vector2::new(0.75, 1.)]);
+
+        // Triangle with its tip poking into the square from above
+        let spike = Hull::new(vec![
+            Vector2::new(-1., 3.),
+            Vector2::new(1., 3.),
+            Vector2::new(0., 0.5),
+        ], 0.);
+        let contact = intersect(&square(0., 0., 2.), &spike).unwrap();
+        assert_eq!(contact.normal, Vector2::new(0., 1.));
+        assert_eq!(contact.depth, 0.5);
+
+        // The slope of the triangle misses the corner of the square
+        let slope = Hull::new(vec![
+            Vector2::new(3., 0.5),
+            Vector2::new(3., 3.),
+            Vector2::new(0.5, 3.),
+        ], 0.);
+        assert!(intersect(&square(0., 0., 2.), &slope).is_none());
+    }
+
+    #[test]
+    fn test_polygon_circle() {
+        let circle = |x: f32, y: f32, radius: f32| Hull::new(vec![Vector2::new(x, y)], radius);
+
+        // Misses the corner
+        assert!(intersect(&square(0., 0., 2.), &circle(1.6, 1.6, 0.8)).is_none());
+        // Hits the corner
+        assert!(intersect(&square(0., 0., 2.), &circle(1.5, 1.5, 0.8)).is_some());
+
+        let contact = intersect(&circle(0., 2.5, 1.), &square(0., 0., 4.)).unwrap();
+        assert_eq!(contact.normal, Vector2::new(0., -1.));
+        assert_eq!(contact.depth, 0.5);
+        assert_eq!(contact.point, Vector2::new(0., 1.75));
+        assert_eq!(contact.points, vec![Vector2::new(0., 1.75)]);
+    }
+
+    #[test]
+    fn test_time_of_impact() {
+        let motion = Vector2::new(10., 0.);
+
+        let (time, normal) = time_of_impact(&square(0., 0., 2.), &motion, &square(6., 0., 2.)).unwrap();
+        assert!((time * 10. - 4.).abs() <= TOI_TOLERANCE);
+        assert_eq!(normal, Vector2::new(1., 0.));
+
+        // Passes by
+        assert!(time_of_impact(&square(0., 0., 2.), &motion, &square(6., 3., 2.)).is_none());
+        // Too far away
+        assert!(time_of_impact(&square(0., 0., 2.), &motion, &square(20., 0., 2.)).is_none());
+        // Moves away
+        assert!(time_of_impact(&square(0., 0., 2.), &-motion, &square(6., 0., 2.)).is_none());
+
+        // Diagonal motion of a circle towards a corner
+        let circle = Hull::new(vec![Vector2::new(0., 0.)], 1.);
+        let (time, _) = time_of_impact(&circle, &Vector2::new(10., 10.), &square(5., 5., 2.)).unwrap();
+        let distance = (Vector2::new(4., 4.).norm() - 1.) / Vector2::new(10., 10.).norm();
+        assert!((time - distance).abs() <= TOI_TOLERANCE);
+    }
+
+    #[test]
+    fn test_raycast() {
+        let right = Vector2::new(1., 0.);
+
+        let (distance, normal) = square(5., 0., 2.).raycast(&Vector2::new(0., 0.), &right, 100.).unwrap();
+        assert_eq!(distance, 4.);
+        assert_eq!(normal, Vector2::new(-1., 0.));
+
+        // Too short, passes by and points away
+        assert!(square(5., 0., 2.).raycast(&Vector2::new(0., 0.), &right, 3.).is_none());
+        assert!(square(5., 3., 2.).raycast(&Vector2::new(0., 0.), &right, 100.).is_none());
+        assert!(square(5., 0., 2.).raycast(&Vector2::new(0., 0.), &-right, 100.).is_none());
+        // Starts inside
+        assert!(square(0., 0., 2.).raycast(&Vector2::new(0., 0.), &right, 100.).is_none());
+
+        let circle = Hull::new(vec![Vector2::new(5., 0.)], 1.);
+        let (distance, normal) = circle.raycast(&Vector2::new(0., 0.), &right, 100.).unwrap();
+        assert_eq!(distance, 4.);
+        assert_eq!(normal, Vector2::new(-1., 0.));
+
+        // Hits the rounded side of a horizontal capsule from above
+        let capsule = Hull::new(vec![Vector2::new(-2., 0.), Vector2::new(2., 0.)], 1.);
+        let (distance, normal) = capsule.raycast(&Vector2::new(1., 5.), &Vector2::new(0., -1.), 100.).unwrap();
+        assert_eq!(distance, 4.);
+        assert_eq!(normal, Vector2::new(0., 1.));
+
+        // Hits a segment from either side
+        let segment = Hull::new(vec![Vector2::new(0., -1.), Vector2::new(0., 1.)], 0.);
+        assert_eq!(segment.raycast(&Vector2::new(-3., 0.), &right, 100.), Some((3., Vector2::new(-1., 0.))));
+        assert_eq!(segment.raycast(&Vector2::new(3., 0.), &-right, 100.), Some((3., Vector2::new(1., 0.))));
+    }
+}
@@ -2,6 +2,16 @@ pub mod components;
 pub mod systems;
 pub mod events;
 pub mod utils;
+pub mod geometry;
+pub mod broad_phase;
+pub mod solver;
+pub mod character;
+pub mod query;
+
+use crate::{
+    broad_phase::BroadPhase,
+    solver::ContactSolver,
+};
 
 use amethyst::{
     ecs::DispatcherBuilder,
@@ -13,31 +23,97 @@ use amethyst::{
 /// Add all the systems relevant for collisions and movement.
 pub struct ColliderPhysicsBundle {
     handle_collisions: bool,
+    broad_phase: Option<BroadPhase>,
+    contact_solver: Option<ContactSolver>,
+    fixed_step: Option<f32>,
 }
 
 impl ColliderPhysicsBundle {
     pub fn new() -> Self {
         ColliderPhysicsBundle {
             handle_collisions: false,
+            broad_phase: None,
+            contact_solver: None,
+            fixed_step: None,
         }
     }
 
+    /// Choose how to find pairs of colliders that might collide. Defaults to a 'SpatialHashGrid'.
+    ///
+    /// Use 'BroadPhase::DynamicTree' if collider sizes vary a lot and 'BroadPhase::SweepAndPrune'
+    /// if most colliders move only a bit every frame.
+    pub fn with_broad_phase(mut self, broad_phase: BroadPhase) -> Self {
+        self.broad_phase = Some(broad_phase);
+        self
+    }
+
     /// Handle collisions automatically. The way collisions are handled
     /// can be specified via the 'HandleCollisionMode' enum.
     pub fn with_collision_handler(mut self) -> Self {
         self.handle_collisions = true;
         self
     }
+
+    /// Handle collisions automatically, like 'with_collision_handler', but resolve the ones of entities
+    /// with the 'Impulse' mode together, with an iterative solver. Use this for stacks and piles of bodies.
+    ///
+    /// For example, 'ContactSolver::new(10)' solves all contacts 10 times a frame.
+    pub fn with_contact_solver(mut self, solver: ContactSolver) -> Self {
+        self.handle_collisions = true;
+        self.contact_solver = Some(solver);
+        self
+    }
+
+    /// Advance movement, collisions and their handling in steps of 'step' seconds, instead of once a frame
+    /// by the frame time. That way, the simulation behaves the same at any frame rate.
+    ///
+    /// The systems run on the main thread, after all other systems. See 'PhysicsTime' for rendering smoothly.
+    ///
+    /// As they aren't added by name, no other system can depend on "movement_system",
+    /// "character_controller_system", "collision_system" or "handle_collisions_system", and systems
+    /// reading the 'CollisionEvent's get the ones of a frame during the next frame.
+    /// As they run after the 'TransformSystem', the 'GlobalTransform's, and with them the rendered
+    /// positions, lag a frame behind the Transforms.
+    ///
+    /// Panics if 'step' isn't positive.
+    pub fn with_fixed_timestep(mut self, step: f32) -> Self {
+        assert!(step > 0., "The fixed time step needs to be positive, but is {}", step);
+        self.fixed_step = Some(step);
+        self
+    }
 }
 
 impl<'a, 'b> SystemBundle<'a, 'b> for ColliderPhysicsBundle {
     fn build(self, dispatcher: &mut DispatcherBuilder<'a, 'b>) -> Result<(), Error> {
         use self::systems::*;
 
+        let collision_system = match self.broad_phase {
+            Some(broad_phase) => CollisionSystem::with_broad_phase(broad_phase),
+            None => CollisionSystem::default(),
+        };
+
+        let (contact_solver, handle_collisions) = match (self.handle_collisions, self.contact_solver) {
+            (true, Some(solver)) => (Some(ContactSolverSystem::new(solver)),
+                                     Some(HandleCollisionsSystem::without_impulses())),
+            (true, None) => (None, Some(HandleCollisionsSystem::default())),
+            _ => (None, None),
+        };
+
+        if let Some(step) = self.fixed_step {
+            dispatcher.add_thread_local(FixedStepPhysics::new(step, collision_system, contact_solver, handle_collisions));
+            return Ok(())
+        }
+
         dispatcher.add(MovementSystem, "movement_system", &[]);
-        dispatcher.add(CollisionSystem, "collision_system", &["movement_system"]);
-        if self.handle_collisions {
-            dispatcher.add(HandleCollisionsSystem::default(), "handle_collisions_system", &["collision_system"]);
+        dispatcher.add(CharacterControllerSystem, "character_controller_system", &["movement_system"]);
+        dispatcher.add(collision_system, "collision_system", &["character_controller_system"]);
+        let mut last = "collision_system";
+        if let Some(contact_solver) = contact_solver {
+            dispatcher.add(contact_solver, "contact_solver_system", &[last]);
+            last = "contact_solver_system";
+        }
+        if let Some(handle_collisions) = handle_collisions {
+            dispatcher.add(handle_collisions, "handle_collisions_system", &[last]);
         }
         Ok(())
     }
@@ -45,10 +121,10 @@ impl<'a, 'b> SystemBundle<'a, 'b> for ColliderPhysicsBundle {
 
 #[cfg(test)]
 mod physics_tests {
-    use crate::physics::{rotate_vec, reflect_velocity, oppose_collision};
+    use crate::utils::{rotate_vec, reflect_velocity, oppose_collision};
     use amethyst::core::nalgebra::Vector2;
     use std::f32::consts::PI;
-    use crate::physics::components::Velocity;
+    use crate::components::Velocity;
 
     #[test]
     fn test_rotate_vec() {
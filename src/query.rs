@@ -0,0 +1,368 @@
+use amethyst::{
+    ecs::{
+        Entity, Read, ReadStorage, Resources, SystemData, shred::ResourceId,
+    },
+    core::{
+        nalgebra::Vector2,
+        transform::Transform,
+    },
+};
+
+use crate::{
+    broad_phase::BroadPhase,
+    components::{Collider2D, CollisionGroups},
+    geometry::Aabb,
+    utils::ColliderStorages,
+};
+
+use std::cmp::Ordering;
+
+/// Narrows down which colliders a query considers.
+///
+/// By default, every collider except for sensors is considered.
+#[derive(Clone, Debug, Default)]
+pub struct QueryFilter {
+    /// Only colliders that can collide with these groups are considered.
+    pub groups: CollisionGroups,
+    /// These entities are never considered, e.g. the one firing a hitscan weapon.
+    pub excluded: Vec<Entity>,
+    pub include_sensors: bool,
+}
+
+impl QueryFilter {
+    pub fn new() -> Self {
+        QueryFilter::default()
+    }
+
+    pub fn with_groups(mut self, groups: CollisionGroups) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    pub fn excluding(mut self, entity: Entity) -> Self {
+        self.excluded.push(entity);
+        self
+    }
+
+    pub fn with_sensors(mut self) -> Self {
+        self.include_sensors = true;
+        self
+    }
+}
+
+/// Where a ray hit a collider.
+#[derive(Clone, Debug)]
+pub struct RaycastHit {
+    pub entity: Entity,
+    pub point: Vector2<f32>,
+    /// Surface normal of the collider at 'point', pointing against the ray.
+    pub normal: Vector2<f32>,
+    /// Distance from the origin of the ray to 'point'.
+    pub distance: f32,
+}
+
+/// Where a collider moving along a displacement first hits another one.
+#[derive(Clone, Debug)]
+pub struct ShapeCastHit {
+    pub entity: Entity,
+    /// Fraction of the displacement the collider moves before hitting 'entity', between 0 and 1.
+    pub time_of_impact: f32,
+    /// Position of the moving collider at the time of impact.
+    pub position: Vector2<f32>,
+    /// Surface normal of the collider that was hit, pointing towards the moving collider.
+    pub normal: Vector2<f32>,
+}
+
+type CollisionWorldData<'a> = (
+    ColliderStorages<'a>,
+    ReadStorage<'a, Transform>,
+    Read<'a, BroadPhase>,
+);
+
+/// Queries against every active collider in the world, for line-of-sight checks, hitscan weapons,
+/// picking and the like.
+///
+/// Colliders are placed in world space the same way the 'CollisionSystem' does it, including the
+/// scale and rotation of their Transform and the ones of their parents.
+/// Only colliders the 'BroadPhase' finds near the query are tested exactly, see 'ColliderStorages::near'.
+/// Colliders created since the 'CollisionSystem' last ran are always tested, but colliders teleported
+/// since then may be missed. Deactivated colliders are never considered. It reads the 'Transform' storage,
+/// so a system can't use it together with a 'WriteStorage<Transform>'.
+pub struct CollisionWorld<'a> {
+    storages: ColliderStorages<'a>,
+    transforms: ReadStorage<'a, Transform>,
+    broad_phase: Read<'a, BroadPhase>,
+}
+
+impl<'a> SystemData<'a> for CollisionWorld<'a> {
+    fn setup(res: &mut Resources) {
+        <CollisionWorldData<'a> as SystemData>::setup(res);
+    }
+
+    fn fetch(res: &'a Resources) -> Self {
+        let (storages, transforms, broad_phase) = <CollisionWorldData<'a> as SystemData<'a>>::fetch(res);
+
+        CollisionWorld {
+            storages, transforms, broad_phase,
+        }
+    }
+
+    fn reads() -> Vec<ResourceId> {
+        <CollisionWorldData as SystemData>::reads()
+    }
+
+    fn writes() -> Vec<ResourceId> {
+        <CollisionWorldData as SystemData>::writes()
+    }
+}
+
+impl<'a> CollisionWorld<'a> {
+    /// Every collider that passes 'filter' in world space, with its entity and position.
+    ///
+    /// Unlike the queries, this doesn't use the 'BroadPhase', so it's up to date, but slow.
+    pub fn colliders(&self, filter: &QueryFilter) -> Vec<(Entity, Collider2D, Vector2<f32>)> {
+        self.storages.placed(&self.transforms, filter.include_sensors).into_iter()
+            .filter(|(entity, _, _)| self.passes(*entity, filter))
+            .collect()
+    }
+
+    /// Colliders that pass 'filter' and whose bounding box overlaps 'bounds' in world space,
+    /// looked up in the 'BroadPhase'. Sorted by entity, so queries give the same results every time.
+    fn candidates(&self, bounds: &Aabb, filter: &QueryFilter) -> Vec<(Entity, Collider2D, Vector2<f32>)> {
+        self.storages.near(&self.broad_phase, bounds).into_iter()
+            .filter(|entity| (filter.include_sensors || !self.storages.is_sensor(*entity))
+                && self.passes(*entity, filter))
+            .filter_map(|entity| {
+                let (collider, position) = self.storages.place(entity, &self.transforms)?;
+                Some((entity, collider, position))
+            })
+            .filter(|(_, collider, position)| collider.aabb(position).overlaps(bounds))
+            .collect()
+    }
+
+    fn passes(&self, entity: Entity, filter: &QueryFilter) -> bool {
+        !filter.excluded.contains(&entity) && filter.groups.can_collide_with(&self.storages.groups(entity))
+    }
+
+    /// The nearest collider hit by the ray. Colliders containing 'origin' aren't hit.
+    pub fn raycast(&self, origin: &Vector2<f32>, direction: &Vector2<f32>, max_distance: f32,
+                   filter: &QueryFilter) -> Option<RaycastHit> {
+        self.raycast_all(origin, direction, max_distance, filter).into_iter().next()
+    }
+
+    /// Every collider hit by the ray, nearest first. Each entity is hit at most once.
+    pub fn raycast_all(&self, origin: &Vector2<f32>, direction: &Vector2<f32>, max_distance: f32,
+                       filter: &QueryFilter) -> Vec<RaycastHit> {
+        let length = direction.norm();
+        if length <= std::f32::EPSILON {
+            return Vec::new()
+        }
+        let direction = direction / length;
+
+        let end = origin + direction * max_distance;
+        let bounds = Aabb::new(
+            Vector2::new(origin[0].min(end[0]), origin[1].min(end[1])),
+            Vector2::new(origin[0].max(end[0]), origin[1].max(end[1])),
+        );
+
+        let mut hits: Vec<RaycastHit> = self.candidates(&bounds, filter).into_iter()
+            .filter_map(|(entity, collider, position)| {
+                let (distance, normal) = collider.raycast(&position, origin, &direction, max_distance)?;
+                if !distance.is_finite() {
+                    return None
+                }
+                Some(RaycastHit {
+                    entity,
+                    point: origin + direction * distance,
+                    normal,
+                    distance,
+                })
+            })
+            .collect();
+        hits.sort_by(|hit, other| hit.distance.partial_cmp(&other.distance).unwrap_or(Ordering::Equal));
+        hits
+    }
+}
+
+impl<'a> CollisionWorld<'a> {
+    /// The first collider 'collider' hits if it moved from 'position' by 'displacement'.
+    ///
+    /// 'collider' is in world space, like the result of 'Collider2D::transformed_by'.
+    /// Colliders it already overlaps at 'position' aren't hit.
+    pub fn shape_cast(&self, collider: &Collider2D, position: &Vector2<f32>, displacement: &Vector2<f32>,
+                      filter: &QueryFilter) -> Option<ShapeCastHit> {
+        let swept = collider.aabb(position).merged(&collider.aabb(&(position + displacement)));
+
+        self.candidates(&swept, filter).into_iter()
+            .filter_map(|(entity, other, other_position)| {
+                let (time, normal) = collider.time_of_impact(position, displacement, &other, &other_position)?;
+                Some(ShapeCastHit {
+                    entity,
+                    time_of_impact: time,
+                    position: position + displacement * time,
+                    normal: -normal,
+                })
+            })
+            .fold(None, |earliest: Option<ShapeCastHit>, hit| match earliest {
+                Some(earliest) if earliest.time_of_impact <= hit.time_of_impact => Some(earliest),
+                _ => Some(hit),
+            })
+    }
+
+    /// Entities whose collider contains 'point', e.g. for picking entities with the mouse.
+    pub fn entities_at_point(&self, point: &Vector2<f32>, filter: &QueryFilter) -> Vec<Entity> {
+        let bounds = Aabb::new(*point, *point);
+
+        self.candidates(&bounds, filter).into_iter()
+            .filter(|(_, collider, position)| collider.contains_point(position, point))
+            .map(|(entity, _, _)| entity)
+            .collect()
+    }
+
+    /// Entities whose collider overlaps 'aabb'.
+    pub fn entities_in_aabb(&self, aabb: &Aabb, filter: &QueryFilter) -> Vec<Entity> {
+        let size = aabb.max - aabb.min;
+        let area = Collider2D::rect_without_offset(size[0], size[1]);
+        self.entities_overlapping(&area, &((aabb.min + aabb.max) * 0.5), filter)
+    }
+
+    /// Entities whose collider overlaps 'collider', e.g. the radius of an explosion.
+    ///
+    /// 'collider' is in world space, like the result of 'Collider2D::transformed_by'.
+    pub fn entities_overlapping(&self, collider: &Collider2D, position: &Vector2<f32>,
+                                filter: &QueryFilter) -> Vec<Entity> {
+        let bounds = collider.aabb(position);
+
+        self.candidates(&bounds, filter).into_iter()
+            .filter(|(_, other, other_position)| collider.collides_with(position, other, other_position))
+            .map(|(entity, _, _)| entity)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_query {
+    use crate::query::{CollisionWorld, QueryFilter};
+    use crate::components::{Collider2D, DeactivateCollider, Sensor};
+    use crate::systems::CollisionSystem;
+    use amethyst::core::{nalgebra::Vector2, transform::Transform};
+    use amethyst::ecs::{World, Builder, Entity, SystemData, RunNow};
+
+    fn place(world: &mut World, collider: Collider2D, x: f32, y: f32) -> Entity {
+        let mut transform = Transform::default();
+        transform.translate_x(x);
+        transform.translate_y(y);
+        world.create_entity().with(collider).with(transform).build()
+    }
+
+    /// Queries find colliders through the broad phase, which the 'CollisionSystem' updates.
+    fn update_broad_phase(world: &mut World) {
+        let mut collision_system = CollisionSystem::default();
+        RunNow::setup(&mut collision_system, &mut world.res);
+        collision_system.run_now(&world.res);
+    }
+
+    #[test]
+    fn test_raycast() {
+        let mut world = World::new();
+        <CollisionWorld as SystemData>::setup(&mut world.res);
+
+        let near = place(&mut world, Collider2D::rect_without_offset(2., 2.), 10., 0.);
+        let far = place(&mut world, Collider2D::circle_without_offset(1.), 20., 0.);
+        let deactivated = place(&mut world, Collider2D::rect_without_offset(2., 2.), 5., 0.);
+        world.write_storage::<DeactivateCollider>().insert(deactivated, DeactivateCollider).unwrap();
+        let sensor = place(&mut world, Collider2D::rect_without_offset(2., 2.), 3., 0.);
+        world.write_storage::<Sensor>().insert(sensor, Sensor).unwrap();
+
+        update_broad_phase(&mut world);
+        let collision_world = world.system_data::<CollisionWorld>();
+        let (origin, right) = (Vector2::new(0., 0.), Vector2::new(1., 0.));
+
+        let hit = collision_world.raycast(&origin, &right, 100., &QueryFilter::new()).unwrap();
+        assert_eq!(hit.entity, near);
+        assert_eq!(hit.point, Vector2::new(9., 0.));
+        assert_eq!(hit.normal, Vector2::new(-1., 0.));
+        assert_eq!(hit.distance, 9.);
+
+        let hits = collision_world.raycast_all(&origin, &right, 100., &QueryFilter::new());
+        assert_eq!(hits.iter().map(|hit| hit.entity).collect::<Vec<_>>(), vec![near, far]);
+
+        let filter = QueryFilter::new().excluding(near).with_sensors();
+        let hits = collision_world.raycast_all(&origin, &right, 100., &filter);
+        assert_eq!(hits.iter().map(|hit| hit.entity).collect::<Vec<_>>(), vec![sensor, far]);
+
+        assert!(collision_world.raycast(&origin, &right, 5., &QueryFilter::new()).is_none());
+    }
+
+    #[test]
+    fn test_shape_cast() {
+        let mut world = World::new();
+        <CollisionWorld as SystemData>::setup(&mut world.res);
+
+        let wall = place(&mut world, Collider2D::rect_without_offset(2., 20.), 10., 0.);
+        place(&mut world, Collider2D::rect_without_offset(2., 20.), 20., 0.);
+        update_broad_phase(&mut world);
+        let collision_world = world.system_data::<CollisionWorld>();
+
+        let agent = Collider2D::circle_without_offset(1.);
+        let hit = collision_world.shape_cast(&agent, &Vector2::new(0., 0.), &Vector2::new(40., 0.),
+                                             &QueryFilter::new()).unwrap();
+        assert_eq!(hit.entity, wall);
+        assert!((hit.time_of_impact * 40. - 8.).abs() < 0.05);
+        assert!((hit.position - Vector2::new(8., 0.)).norm() < 0.05);
+        assert_eq!(hit.normal, Vector2::new(-1., 0.));
+
+        assert!(collision_world.shape_cast(&agent, &Vector2::new(0., 0.), &Vector2::new(5., 0.),
+                                           &QueryFilter::new()).is_none());
+        assert!(collision_world.shape_cast(&agent, &Vector2::new(0., 0.), &Vector2::new(0., 40.),
+                                           &QueryFilter::new()).is_none());
+    }
+
+    #[test]
+    fn test_overlap_queries() {
+        use crate::geometry::Aabb;
+
+        let mut world = World::new();
+        <CollisionWorld as SystemData>::setup(&mut world.res);
+
+        let square = place(&mut world, Collider2D::rect_without_offset(2., 2.), 0., 0.);
+        let ball = place(&mut world, Collider2D::circle_without_offset(1.), 5., 0.);
+        let ground = place(&mut world, Collider2D::segment_without_offset(Vector2::new(-10., -3.), Vector2::new(10., -3.)), 0., 0.);
+        update_broad_phase(&mut world);
+        let collision_world = world.system_data::<CollisionWorld>();
+        let filter = QueryFilter::new();
+
+        assert_eq!(collision_world.entities_at_point(&Vector2::new(0.5, 0.5), &filter), vec![square]);
+        assert_eq!(collision_world.entities_at_point(&Vector2::new(5.9, 0.), &filter), vec![ball]);
+        // Inside the bounding box of the ball, but not the ball
+        assert!(collision_world.entities_at_point(&Vector2::new(5.9, 0.9), &filter).is_empty());
+
+        let aabb = Aabb::new(Vector2::new(0.5, -4.), Vector2::new(3.5, -0.5));
+        assert_eq!(collision_world.entities_in_aabb(&aabb, &filter), vec![square, ground]);
+
+        let explosion = Collider2D::circle_without_offset(3.);
+        assert_eq!(collision_world.entities_overlapping(&explosion, &Vector2::new(3., 0.5), &filter),
+                   vec![square, ball]);
+    }
+
+    #[test]
+    fn test_outdated_broad_phase() {
+        let mut world = World::new();
+        <CollisionWorld as SystemData>::setup(&mut world.res);
+        let filter = QueryFilter::new();
+
+        // Without a 'CollisionSystem', every collider is tested
+        let square = place(&mut world, Collider2D::rect_without_offset(2., 2.), 0., 0.);
+        assert_eq!(world.system_data::<CollisionWorld>().entities_at_point(&Vector2::new(0.5, 0.5), &filter),
+                   vec![square]);
+
+        // Created after the broad phase was updated
+        update_broad_phase(&mut world);
+        let ball = place(&mut world, Collider2D::circle_without_offset(1.), 5., 0.);
+        let collision_world = world.system_data::<CollisionWorld>();
+        assert_eq!(collision_world.entities_at_point(&Vector2::new(5.5, 0.), &filter), vec![ball]);
+        let hit = collision_world.raycast(&Vector2::new(0., 0.), &Vector2::new(1., 0.), 100.,
+                                          &QueryFilter::new().excluding(square)).unwrap();
+        assert_eq!(hit.entity, ball);
+    }
+}
@@ -0,0 +1,237 @@
+use amethyst::core::nalgebra::Vector2;
+
+use crate::events::ContactKey;
+
+use std::collections::HashMap;
+
+/// Approaching speeds below this don't bounce, so resting contacts come to rest.
+const RESTITUTION_THRESHOLD: f32 = 1.;
+
+/// A body as seen by the 'ContactSolver'.
+#[derive(Clone, Debug)]
+pub struct SolverBody {
+    pub velocity: Vector2<f32>,
+    /// Zero for immovable bodies.
+    pub inverse_mass: f32,
+}
+
+/// A contact between two bodies, given as indices into the bodies passed to 'ContactSolver::solve'.
+#[derive(Clone, Debug)]
+pub struct SolverContact {
+    pub key: ContactKey,
+    pub bodies: (usize, usize),
+    /// Unit vector pointing from the first body towards the second one.
+    pub normal: Vector2<f32>,
+    pub depth: f32,
+    /// Number of contact points. Every point gets its own impulses.
+    pub points: usize,
+    pub restitution: f32,
+    pub friction: f32,
+}
+
+/// Impulses of a single contact point, accumulated over all iterations.
+#[derive(Clone, Copy, Debug, Default)]
+struct AccumulatedImpulse {
+    normal: f32,
+    tangent: f32,
+}
+
+struct ContactPoint {
+    contact: usize,
+    /// Index of the point within its contact.
+    index: usize,
+    impulse: AccumulatedImpulse,
+    /// Separating speed the normal impulse aims for.
+    bias: f32,
+}
+
+/// Resolves all contacts of a frame together with sequential impulses.
+///
+/// Every iteration applies an impulse at every contact point, clamped so that the impulses
+/// accumulated over the frame only ever push the bodies apart and friction stays within its cone.
+/// More iterations make stacks of bodies more stable.
+///
+/// The impulses of the last frame are applied before the first iteration (warm starting),
+/// so resting contacts start off close to their solution.
+/// Penetration is corrected by pushing the bodies apart by a fraction of it every frame (Baumgarte stabilization).
+#[derive(Clone, Debug)]
+pub struct ContactSolver {
+    pub iterations: usize,
+    /// Fraction of the penetration that gets corrected every frame.
+    pub baumgarte: f32,
+    /// Penetration that gets tolerated, so resting bodies don't jitter.
+    pub slop: f32,
+    pub warm_starting: bool,
+    cache: HashMap<(ContactKey, usize), AccumulatedImpulse>,
+}
+
+impl ContactSolver {
+    pub fn new(iterations: usize) -> Self {
+        ContactSolver {
+            iterations,
+            baumgarte: 0.2,
+            slop: 0.5,
+            warm_starting: true,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Changes the velocities of 'bodies' so that they stop approaching each other at 'contacts'.
+    ///
+    /// 'delta' is the time in seconds the contacts are resolved for, used to correct the penetration.
+    pub fn solve(&mut self, bodies: &mut [SolverBody], contacts: &[SolverContact], delta: f32) {
+        let mut points = Vec::new();
+        for (index, contact) in contacts.iter().enumerate() {
+            let (first, second) = (&bodies[contact.bodies.0], &bodies[contact.bodies.1]);
+            let normal_speed = (second.velocity - first.velocity).dot(&contact.normal);
+
+            let bounce = if -normal_speed > RESTITUTION_THRESHOLD { -normal_speed * contact.restitution } else { 0. };
+            let correction = if delta > 0. {
+                self.baumgarte / delta * (contact.depth - self.slop).max(0.)
+            } else {
+                0.
+            };
+
+            for point in 0..contact.points.max(1) {
+                let impulse = if self.warm_starting {
+                    self.cache.get(&(contact.key, point)).cloned().unwrap_or_default()
+                } else {
+                    AccumulatedImpulse::default()
+                };
+                points.push(ContactPoint {
+                    contact: index,
+                    index: point,
+                    impulse,
+                    bias: bounce.max(correction),
+                });
+            }
+        }
+
+        for point in &points {
+            let contact = &contacts[point.contact];
+            let impulse = contact.normal * point.impulse.normal + tangent(&contact.normal) * point.impulse.tangent;
+            apply(bodies, contact, &impulse);
+        }
+
+        for _ in 0..self.iterations {
+            for point in &mut points {
+                let contact = &contacts[point.contact];
+                let inverse_mass_sum = bodies[contact.bodies.0].inverse_mass + bodies[contact.bodies.1].inverse_mass;
+                if inverse_mass_sum <= 0. {
+                    continue
+                }
+
+                // Friction first, limited by the current normal impulse
+                let tangent = tangent(&contact.normal);
+                let tangent_speed = relative_velocity(bodies, contact).dot(&tangent);
+                let max_friction = contact.friction * point.impulse.normal;
+                let accumulated = (point.impulse.tangent - tangent_speed / inverse_mass_sum)
+                    .max(-max_friction).min(max_friction);
+                apply(bodies, contact, &(tangent * (accumulated - point.impulse.tangent)));
+                point.impulse.tangent = accumulated;
+
+                let normal_speed = relative_velocity(bodies, contact).dot(&contact.normal);
+                let accumulated = (point.impulse.normal + (-normal_speed + point.bias) / inverse_mass_sum).max(0.);
+                apply(bodies, contact, &(contact.normal * (accumulated - point.impulse.normal)));
+                point.impulse.normal = accumulated;
+            }
+        }
+
+        // Only keep the contacts of this frame, ended ones shouldn't warm start anything
+        self.cache = points.iter()
+            .map(|point| ((contacts[point.contact].key, point.index), point.impulse))
+            .collect();
+    }
+}
+
+impl Default for ContactSolver {
+    fn default() -> Self {
+        ContactSolver::new(8)
+    }
+}
+
+fn tangent(normal: &Vector2<f32>) -> Vector2<f32> {
+    Vector2::new(-normal[1], normal[0])
+}
+
+fn relative_velocity(bodies: &[SolverBody], contact: &SolverContact) -> Vector2<f32> {
+    bodies[contact.bodies.1].velocity - bodies[contact.bodies.0].velocity
+}
+
+/// Applies 'impulse' to the second body of the contact and its opposite to the first one.
+fn apply(bodies: &mut [SolverBody], contact: &SolverContact, impulse: &Vector2<f32>) {
+    let first = &mut bodies[contact.bodies.0];
+    first.velocity -= impulse * first.inverse_mass;
+    let second = &mut bodies[contact.bodies.1];
+    second.velocity += impulse * second.inverse_mass;
+}
+
+#[cfg(test)]
+mod test_solver {
+    use crate::solver::{ContactSolver, SolverBody, SolverContact};
+    use amethyst::core::nalgebra::Vector2;
+    use amethyst::ecs::{World, Builder};
+
+    fn resting(world: &mut World, depth: f32) -> (Vec<SolverBody>, Vec<SolverContact>) {
+        let crate_entity = world.create_entity().build();
+        let ground = world.create_entity().build();
+
+        let bodies = vec![
+            SolverBody { velocity: Vector2::new(0., -1.), inverse_mass: 1. },
+            SolverBody { velocity: Vector2::new(0., 0.), inverse_mass: 0. },
+        ];
+        let contacts = vec![SolverContact {
+            key: (crate_entity, None, ground, None),
+            bodies: (0, 1),
+            normal: Vector2::new(0., -1.),
+            depth,
+            points: 2,
+            restitution: 0.,
+            friction: 0.5,
+        }];
+        (bodies, contacts)
+    }
+
+    #[test]
+    fn test_resting_contact() {
+        let mut world = World::new();
+        let (mut bodies, contacts) = resting(&mut world, 0.1);
+
+        let mut solver = ContactSolver::new(4);
+        solver.solve(&mut bodies, &contacts, 0.1);
+        assert!(bodies[0].velocity.norm() < 1e-5);
+        assert_eq!(bodies[1].velocity, Vector2::new(0., 0.));
+    }
+
+    #[test]
+    fn test_warm_starting() {
+        let mut world = World::new();
+        let (mut bodies, contacts) = resting(&mut world, 0.1);
+
+        let mut solver = ContactSolver::new(4);
+        solver.solve(&mut bodies, &contacts, 0.1);
+
+        // The impulses of the last frame alone keep the crate from falling in
+        solver.iterations = 0;
+        bodies[0].velocity = Vector2::new(0., -1.);
+        solver.solve(&mut bodies, &contacts, 0.1);
+        assert!(bodies[0].velocity.norm() < 1e-5);
+
+        solver.warm_starting = false;
+        bodies[0].velocity = Vector2::new(0., -1.);
+        solver.solve(&mut bodies, &contacts, 0.1);
+        assert_eq!(bodies[0].velocity, Vector2::new(0., -1.));
+    }
+
+    #[test]
+    fn test_position_correction() {
+        let mut world = World::new();
+        let (mut bodies, contacts) = resting(&mut world, 1.5);
+
+        let mut solver = ContactSolver::new(8);
+        solver.solve(&mut bodies, &contacts, 0.1);
+        // Pushed out of the ground with 0.2 / 0.1 * (1.5 - 0.5)
+        assert!((bodies[0].velocity[1] - 2.).abs() < 1e-4);
+        assert!(bodies[0].velocity[0].abs() < 1e-5);
+    }
+}
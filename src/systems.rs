@@ -1,85 +1,405 @@
 use amethyst::{
     ecs::{
         System, SystemData, Join,
-        Read, Write, ReadStorage, WriteStorage, Entities,
-        Entity, Resources,
+        Read, Write, ReadStorage, WriteStorage,
+        Entity, Resources, Storage, storage::MaskedStorage, RunNow,
     },
     core::{
         shrev::{
             EventChannel, ReaderId,
         },
         timing::Time,
-        transform::Transform,
+        transform::{Transform, Parent},
+        nalgebra::Vector2,
     },
 };
 
+use std::collections::{HashMap, HashSet};
+use std::ops::Deref;
+
 use crate::{
-    events::CollisionEvent,
+    broad_phase::{BroadPhase, Proxy},
+    solver::{ContactSolver, SolverBody, SolverContact},
+    geometry::TOI_TOLERANCE,
+    character::{move_and_slide, move_bounds, Obstacle},
+    events::{CollisionEvent, CollisionKind, ContactKey},
     components::*,
     utils::{
-        handle_collision, HandleCollisionStorages,
+        handle_collision, resolve_impulse, integrate_velocity, ImpulseBody, HandleCollisionStorages,
+        ColliderStorages, local_displacement,
     },
 };
 
-use std::collections::HashSet;
+/// A collider in world space, ready to be tested.
+struct PlacedCollider {
+    entity: Entity,
+    collider: Collider2D,
+    position: Vector2<f32>,
+    is_passive: bool,
+    is_sensor: bool,
+    groups: CollisionGroups,
+}
 
 /// Test for collisions and sent them to EventChannel<CollisionEvent>.s
-pub struct CollisionSystem;
+///
+/// Colliders are tested in world space, so entities with a 'Parent' collide where they are rendered.
+/// Only colliders the 'BroadPhase' resource deems close enough are tested,
+/// pairs filtered out by their 'CollisionGroups' are skipped before testing them.
+///
+/// Contacts are tracked across frames, see 'CollisionKind'.
+/// Contacts passing through a 'OneWayPlatform' aren't reported at all.
+#[derive(Default)]
+pub struct CollisionSystem {
+    broad_phase: Option<BroadPhase>,
+    contacts: HashMap<ContactKey, CollisionEvent>,
+    /// Contacts that started out passing through a one-way platform.
+    passing: HashSet<ContactKey>,
+}
+
+impl CollisionSystem {
+    /// The broad phase is inserted as a resource during setup.
+    pub fn with_broad_phase(broad_phase: BroadPhase) -> Self {
+        CollisionSystem {
+            broad_phase: Some(broad_phase),
+            contacts: HashMap::new(),
+            passing: HashSet::new(),
+        }
+    }
+}
 
 impl<'a> System<'a> for CollisionSystem {
     type SystemData = (
-        Entities<'a>,
         Write<'a, EventChannel<CollisionEvent>>,
-        ReadStorage<'a, Collider2D>,
+        Write<'a, BroadPhase>,
+        ColliderStorages<'a>,
         ReadStorage<'a, Transform>,
-        ReadStorage<'a, DeactivateCollider>,
         ReadStorage<'a, PassiveCollider>,
+        ReadStorage<'a, OneWayPlatform>,
+        ReadStorage<'a, Velocity>,
     );
 
-    fn run(&mut self, (entities, mut channel, colliders, transforms, deactivations, passive): Self::SystemData) {
-        let mut covered: HashSet<Entity> = HashSet::new();
+    fn run(&mut self, (mut channel, mut broad_phase, storages, transforms,
+                       passive, platforms, velocities): Self::SystemData) {
+        // Place every collider in world space once, instead of once per tested pair
+        let placed: Vec<PlacedCollider> = storages.placed(&transforms, true).into_iter()
+            .map(|(entity, collider, position)| PlacedCollider {
+                entity,
+                collider,
+                position,
+                is_passive: passive.contains(entity),
+                is_sensor: storages.is_sensor(entity),
+                groups: storages.groups(entity),
+            })
+            .collect();
+
+        let proxies: Vec<Proxy> = placed.iter()
+            .map(|placed| Proxy {
+                entity: placed.entity,
+                aabb: placed.collider.aabb(&placed.position),
+                is_static: placed.is_passive,
+            })
+            .collect();
+
+        // The active entity comes first. If both are active, the one that was joined first does.
+        let mut pairs: Vec<(usize, usize)> = broad_phase.pairs(&proxies).into_iter()
+            .filter(|(first, second)| placed[*first].groups.can_collide_with(&placed[*second].groups))
+            .filter_map(|(first, second)| match (placed[first].is_passive, placed[second].is_passive) {
+                (true, true) => None,
+                (true, false) => Some((second, first)),
+                _ => Some((first, second)),
+            })
+            .collect();
+        pairs.sort();
 
-        for (entity, collider, transform, _, _) in (&entities, &colliders, &transforms, !&deactivations, !&passive).join() {
-            covered.insert(entity);
+        let mut contacts = HashMap::new();
+        let mut passing = HashSet::new();
+        for (first, second) in pairs {
+            let (first, second) = (&placed[first], &placed[second]);
 
-            for (other, other_collider, other_transform, _) in (&entities, &colliders, &transforms, !&deactivations).join() {
-                if covered.contains(&other) {
+            for mut event in CollisionEvent::from_world_collisions(first.entity, second.entity,
+                                                                   &first.collider, &first.position,
+                                                                   &second.collider, &second.position) {
+                event.is_sensor = first.is_sensor || second.is_sensor;
+
+                let key = event.contact_key();
+                let starts_passing = !self.contacts.contains_key(&key)
+                    && passes_one_way_platform(&event, &platforms, &velocities);
+                if self.passing.contains(&key) || starts_passing {
+                    passing.insert(key);
                     continue
                 }
-                if let Some(event) = CollisionEvent::from_collision(entity, other,
-                                                                    collider, other_collider,
-                                                                    transform, other_transform) {
-                    channel.single_write(event);
+
+                if self.contacts.contains_key(&key) {
+                    event.kind = CollisionKind::Persisting;
                 }
+                contacts.insert(key, event.clone());
+                channel.single_write(event);
             }
         }
+
+        let mut ended: Vec<_> = self.contacts.drain()
+            .filter(|(key, _)| !contacts.contains_key(key))
+            .collect();
+        ended.sort_by_key(|(key, _)| *key);
+        channel.iter_write(ended.into_iter().map(|(_, mut event)| {
+            event.kind = CollisionKind::Ended;
+            event
+        }));
+
+        self.contacts = contacts;
+        self.passing = passing;
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        if let Some(broad_phase) = self.broad_phase.take() {
+            res.insert(broad_phase);
+        }
     }
 }
 
+/// Whether either entity of the collision is a 'OneWayPlatform' the other one passes through.
+fn passes_one_way_platform<P, V>(event: &CollisionEvent, platforms: &Storage<'_, OneWayPlatform, P>,
+                                 velocities: &Storage<'_, Velocity, V>) -> bool
+    where
+        P: Deref<Target = MaskedStorage<OneWayPlatform>>,
+        V: Deref<Target = MaskedStorage<Velocity>>,
+{
+    let velocity = |entity: Entity| velocities.get(entity).map_or(Vector2::new(0., 0.), |velocity| velocity.0);
+
+    (0..2).any(|index| match platforms.get(event.collisions[index].entity) {
+        Some(platform) => {
+            let other = event.collisions[1 - index].entity;
+            let relative_velocity = velocity(other) - velocity(event.collisions[index].entity);
+            platform.lets_pass(&event.normal(index), &relative_velocity)
+        }
+        None => false,
+    })
+}
+
 /// Based on the 'HandleCollisionMode' of an Entity. For example, if the collision mode is
 /// 'Reflect', then the Entity performs an elastic collision. This can be turned off for an
 /// Entity by either not registering a 'HandleCollisionMode' for that Entity or setting it to 'Ignore'.
+/// Collisions involving a 'Sensor' are never handled. Ones passing through a 'OneWayPlatform'
+/// aren't reported by the 'CollisionSystem' in the first place.
+///
+/// Entities with the 'Impulse' mode exchange momentum with their collision partner,
+/// based on the 'RigidBody2D' of both entities.
+///
+/// Handled entities are also pushed out of each other along the contact normal. If both entities
+/// of a collision get handled, each one is moved by half the penetration depth. If the other one is
+/// a 'PassiveCollider' or doesn't get handled, the entity is moved by the whole depth.
+/// 'PassiveCollider's themselves aren't pushed.
+/// An entity is moved once after reading all events of the frame. Pushes along the same direction
+/// don't add up, e.g. for an entity resting across two tiles, only the deepest one counts.
 #[derive(Default)]
 pub struct HandleCollisionsSystem {
-    reader: Option<ReaderId<CollisionEvent>>
+    reader: Option<ReaderId<CollisionEvent>>,
+    skip_impulses: bool,
+}
+
+impl HandleCollisionsSystem {
+    /// Leaves entities with the 'Impulse' mode to the 'ContactSolverSystem'.
+    pub fn without_impulses() -> Self {
+        HandleCollisionsSystem {
+            reader: None,
+            skip_impulses: true,
+        }
+    }
 }
 
 impl<'a> System<'a> for HandleCollisionsSystem {
     type SystemData = (
         Read<'a, EventChannel<CollisionEvent>>,
         HandleCollisionStorages<'a>,
+        WriteStorage<'a, Transform>,
+        ReadStorage<'a, Parent>,
+        ReadStorage<'a, PassiveCollider>,
+        ReadStorage<'a, RigidBody2D>,
+    );
+
+    fn run(&mut self, (channel, mut handle, mut transforms, parents, passive, bodies): Self::SystemData) {
+        let mut pushes: HashMap<Entity, Vec<Vector2<f32>>> = HashMap::new();
+
+        for event in channel.read(self.reader.as_mut().unwrap()) {
+            if event.kind == CollisionKind::Ended || event.is_sensor {
+                continue
+            }
+
+            let entities = [event.collisions[0].entity, event.collisions[1].entity];
+            let handled = [handle.handles(entities[0]), handle.handles(entities[1])];
+
+            let impulse_body = |entity: Entity| {
+                let body = bodies.get(entity).cloned().unwrap_or_default();
+                let is_dynamic = match handle.mode(entity) {
+                    Some(HandleCollisionMode::Impulse) => !passive.contains(entity),
+                    _ => false,
+                };
+                ImpulseBody {
+                    velocity: handle.velocity(entity).map_or(Vector2::new(0., 0.), |velocity| velocity.0),
+                    inverse_mass: if is_dynamic { body.inverse_mass() } else { 0. },
+                    restitution: body.restitution,
+                    friction: body.friction,
+                }
+            };
+            let (first, second) = (impulse_body(entities[0]), impulse_body(entities[1]));
+            if !self.skip_impulses && (first.inverse_mass > 0. || second.inverse_mass > 0.) {
+                let (first_velocity, second_velocity) = resolve_impulse(&first, &second, &event.manifold.normal);
+                for (entity, body, velocity) in [(entities[0], first, first_velocity),
+                                                 (entities[1], second, second_velocity)].iter() {
+                    if body.inverse_mass > 0. {
+                        if let Some(current) = handle.velocity_mut(*entity) {
+                            current.0 = *velocity;
+                        }
+                    }
+                }
+            }
+
+            for (index, entity) in entities.iter().enumerate() {
+                if let Some(comps) = handle.get_components(*entity) {
+                    handle_collision(&event.normal(index), comps);
+                }
+                if !handled[index] || passive.contains(*entity) {
+                    continue
+                }
+                if self.skip_impulses {
+                    if let Some(HandleCollisionMode::Impulse) = handle.mode(*entity) {
+                        continue
+                    }
+                }
+
+                let other = 1 - index;
+                let share = if handled[other] && !passive.contains(entities[other]) { 0.5 } else { 1. };
+                let push = -event.normal(index) * (event.manifold.depth * share);
+                accumulate_push(pushes.entry(*entity).or_insert_with(Vec::new), push);
+            }
+        }
+
+        for (entity, pushes) in pushes {
+            let push = pushes.iter().fold(Vector2::new(0., 0.), |sum, push| sum + push);
+            let displacement = local_displacement(entity, &push, &transforms, &parents);
+            if let Some(transform) = transforms.get_mut(entity) {
+                transform.translate_x(displacement[0]);
+                transform.translate_y(displacement[1]);
+            }
+        }
+    }
+
+    fn setup(&mut self, res: &mut Resources) {
+        Self::SystemData::setup(res);
+
+        let reader = res
+            .fetch_mut::<EventChannel<CollisionEvent>>()
+            .register_reader();
+
+        self.reader = Some(reader);
+    }
+}
+
+/// Adds 'push' to the pushes of an entity. Of pushes along the same direction, only the longest one is kept.
+fn accumulate_push(pushes: &mut Vec<Vector2<f32>>, push: Vector2<f32>) {
+    let length = push.norm();
+    if length <= std::f32::EPSILON {
+        return
+    }
+
+    // Directions less than about 2.5 degrees apart count as the same
+    match pushes.iter_mut().find(|other| other.dot(&push) >= 0.999 * other.norm() * length) {
+        Some(other) => if length > other.norm() {
+            *other = push;
+        },
+        None => pushes.push(push),
+    }
+}
+
+/// Resolves the collisions of all entities with the 'Impulse' mode together, using a 'ContactSolver'.
+///
+/// Unlike the 'HandleCollisionsSystem', which resolves every collision once, this keeps stacks
+/// of bodies from jittering and sinking into each other. Penetration is corrected through the
+/// velocities as well, so Transforms are left to the 'MovementSystem'.
+/// Collisions involving a 'Sensor' are never resolved.
+pub struct ContactSolverSystem {
+    solver: ContactSolver,
+    reader: Option<ReaderId<CollisionEvent>>,
+}
+
+impl ContactSolverSystem {
+    pub fn new(solver: ContactSolver) -> Self {
+        ContactSolverSystem {
+            solver,
+            reader: None,
+        }
+    }
+}
+
+impl Default for ContactSolverSystem {
+    fn default() -> Self {
+        ContactSolverSystem::new(ContactSolver::default())
+    }
+}
+
+impl<'a> System<'a> for ContactSolverSystem {
+    type SystemData = (
+        Read<'a, EventChannel<CollisionEvent>>,
+        Read<'a, Time>,
+        Read<'a, PhysicsTime>,
+        HandleCollisionStorages<'a>,
+        ReadStorage<'a, PassiveCollider>,
+        ReadStorage<'a, RigidBody2D>,
     );
 
-    fn run(&mut self, (channel, mut handle): Self::SystemData) {
+    fn run(&mut self, (channel, time, physics_time, mut handle, passive, rigid_bodies): Self::SystemData) {
+        let mut indices: HashMap<Entity, usize> = HashMap::new();
+        let mut entities = Vec::new();
+        let mut bodies = Vec::new();
+        let mut contacts = Vec::new();
+
         for event in channel.read(self.reader.as_mut().unwrap()) {
-            let collisions = (&event.collisions[0], &event.collisions[1]);
+            if event.kind == CollisionKind::Ended || event.is_sensor {
+                continue
+            }
 
-            if let Some(comps) = handle.get_components(collisions.0.entity) {
-                handle_collision(collisions.0, collisions.1, comps);
+            let is_dynamic = |entity: Entity| match handle.mode(entity) {
+                Some(HandleCollisionMode::Impulse) => handle.handles(entity) && !passive.contains(entity),
+                _ => false,
+            };
+            let (first, second) = (event.collisions[0].entity, event.collisions[1].entity);
+            if !is_dynamic(first) && !is_dynamic(second) {
+                continue
             }
 
-            if let Some(comps) = handle.get_components(collisions.1.entity) {
-                handle_collision(collisions.1, collisions.0, comps);
+            let mut body_index = |entity: Entity| *indices.entry(entity).or_insert_with(|| {
+                let rigid_body = rigid_bodies.get(entity).cloned().unwrap_or_default();
+                entities.push(entity);
+                bodies.push(SolverBody {
+                    velocity: handle.velocity(entity).map_or(Vector2::new(0., 0.), |velocity| velocity.0),
+                    inverse_mass: if is_dynamic(entity) { rigid_body.inverse_mass() } else { 0. },
+                });
+                entities.len() - 1
+            });
+            let body_indices = (body_index(first), body_index(second));
+
+            let (first_body, second_body) = (rigid_bodies.get(first).cloned().unwrap_or_default(),
+                                             rigid_bodies.get(second).cloned().unwrap_or_default());
+            contacts.push(SolverContact {
+                key: event.contact_key(),
+                bodies: body_indices,
+                normal: event.manifold.normal,
+                depth: event.manifold.depth,
+                points: event.manifold.points.len(),
+                restitution: first_body.restitution.max(second_body.restitution),
+                friction: (first_body.friction * second_body.friction).sqrt(),
+            });
+        }
+
+        self.solver.solve(&mut bodies, &contacts, physics_time.delta_seconds(&time));
+
+        for (entity, body) in entities.into_iter().zip(bodies) {
+            if body.inverse_mass > 0. {
+                if let Some(velocity) = handle.velocity_mut(entity) {
+                    velocity.0 = body.velocity;
+                }
             }
         }
     }
@@ -96,23 +416,615 @@ impl<'a> System<'a> for HandleCollisionsSystem {
 }
 
 /// Update the entities positions based on their 'Velocity' component.
+///
+/// Velocities are in world space, even for entities with a 'Parent'.
+/// Before moving, velocities are changed by 'Gravity', 'Acceleration' and 'Force',
+/// then slowed down by 'Damping' and limited to 'MaxSpeed'.
+/// Entities with 'ContinuousCollision' that move further than half their size within a frame
+/// stop at the first collider they hit, slightly overlapping it, so the 'CollisionSystem' reports the collision.
+/// The colliders in their way are looked up in the 'BroadPhase', see 'ColliderStorages::near'.
+/// 'OneWayPlatform's they pass through don't stop them.
 pub struct MovementSystem;
 
 impl<'a> System<'a> for MovementSystem {
     type SystemData = (
+        ColliderStorages<'a>,
         WriteStorage<'a, Transform>,
+        WriteStorage<'a, Velocity>,
+        Read<'a, Time>,
+        Read<'a, PhysicsTime>,
+        ReadStorage<'a, ContinuousCollision>,
+        ReadStorage<'a, OneWayPlatform>,
+        Read<'a, BroadPhase>,
+        Read<'a, Gravity>,
+        ReadStorage<'a, GravityScale>,
+        ReadStorage<'a, Acceleration>,
+        ReadStorage<'a, Force>,
+        ReadStorage<'a, RigidBody2D>,
+        ReadStorage<'a, Damping>,
+        ReadStorage<'a, MaxSpeed>,
+    );
+
+    fn run(&mut self, (storages, mut transforms, mut velocities, time, physics_time, continuous, platforms, broad_phase,
+                       gravity, gravity_scales, accelerations, forces, bodies, dampings, max_speeds): Self::SystemData) {
+        let (entities, parents) = (storages.entities(), storages.parents());
+        let delta = physics_time.delta_seconds(&time);
+
+        for (entity, velocity) in (entities, &mut velocities).join() {
+            let mut acceleration = gravity.0 * gravity_scales.get(entity).map_or(1., |scale| scale.0);
+            if let Some(extra) = accelerations.get(entity) {
+                acceleration += extra.0;
+            }
+            if let Some(force) = forces.get(entity) {
+                acceleration += force.0 * bodies.get(entity).cloned().unwrap_or_default().inverse_mass();
+            }
+
+            integrate_velocity(&mut velocity.0, &acceleration, dampings.get(entity).map_or(0., |damping| damping.0),
+                               max_speeds.get(entity).map(|max_speed| max_speed.0), delta);
+        }
+
+        let mut displacements: HashMap<Entity, Vector2<f32>> = (entities, &velocities, &transforms).join()
+            .map(|(entity, velocity, _)| (entity, velocity.0 * delta))
+            .collect();
+
+        let motion_of = |entity: Entity, displacements: &HashMap<Entity, Vector2<f32>>|
+            displacements.get(&entity).cloned().unwrap_or_else(|| Vector2::new(0., 0.));
+        let mut clamped = Vec::new();
+        for (entity, _) in (entities, &continuous).join() {
+            let (collider, position) = match storages.place(entity, &transforms) {
+                Some(placed) => placed,
+                None => continue,
+            };
+            let motion = motion_of(entity, &displacements);
+            if storages.is_sensor(entity) || motion.norm() <= 0.5 * collider.width().min(collider.height()) {
+                continue
+            }
+            let swept = collider.aabb(&position).merged(&collider.aabb(&(position + motion)));
+
+            let earliest = storages.near(&broad_phase, &swept).into_iter()
+                .filter(|other| *other != entity && !storages.is_sensor(*other) && storages.can_collide(entity, *other))
+                .filter_map(|other| {
+                    let (other_collider, other_position) = storages.place(other, &transforms)?;
+                    let relative_motion = motion - motion_of(other, &displacements);
+                    let (time, normal) = collider.time_of_impact(&position, &relative_motion,
+                                                                 &other_collider, &other_position)?;
+                    match platforms.get(other) {
+                        Some(platform) if platform.lets_pass(&-normal, &relative_motion) => None,
+                        _ => Some((time, normal)),
+                    }
+                })
+                .fold(None, |earliest: Option<(f32, Vector2<f32>)>, candidate| match earliest {
+                    Some(earliest) if earliest.0 <= candidate.0 => Some(earliest),
+                    _ => Some(candidate),
+                });
+
+            if let Some((time, normal)) = earliest {
+                clamped.push((entity, motion * time + normal * (2. * TOI_TOLERANCE)));
+            }
+        }
+        displacements.extend(clamped);
+
+        for (entity, displacement) in displacements {
+            let displacement = local_displacement(entity, &displacement, &transforms, parents);
+            if let Some(transform) = transforms.get_mut(entity) {
+                transform.translate_x(displacement[0]);
+                transform.translate_y(displacement[1]);
+            }
+        }
+    }
+}
+
+/// Moves entities with a 'CharacterController2D' by their desired displacement, see 'move_and_slide'.
+///
+/// Characters are stopped by every collider they can collide with according to their 'CollisionGroups',
+/// except for sensors and deactivated colliders. 'OneWayPlatform's only stop them from their solid side,
+/// taking the 'Velocity' of the platform into account. Characters without a 'Collider2D' don't move.
+///
+/// Only colliders the 'BroadPhase' finds near the move are considered, see 'ColliderStorages::near'.
+pub struct CharacterControllerSystem;
+
+impl<'a> System<'a> for CharacterControllerSystem {
+    type SystemData = (
+        ColliderStorages<'a>,
+        WriteStorage<'a, CharacterController2D>,
+        WriteStorage<'a, Transform>,
+        ReadStorage<'a, OneWayPlatform>,
         ReadStorage<'a, Velocity>,
+        Read<'a, BroadPhase>,
         Read<'a, Time>,
+        Read<'a, PhysicsTime>,
     );
 
-    fn run(&mut self, (mut transforms, velocities, time): Self::SystemData) {
-        for (transform, velocity) in (&mut transforms, &velocities).join() {
-            let delta = time.delta_seconds();
+    fn run(&mut self, (storages, mut controllers, mut transforms, platforms, velocities,
+                       broad_phase, time, physics_time): Self::SystemData) {
+        if (&controllers).join().next().is_none() {
+            return
+        }
+
+        let delta = physics_time.delta_seconds(&time);
+        // The boxes in the broad phase are as old as the last run of the 'CollisionSystem',
+        // so look a bit further for colliders that moved since then
+        let mut margin = velocities.join()
+            .map(|velocity| velocity.0.norm() * delta)
+            .fold(0., f32::max);
+
+        for (entity, controller) in (storages.entities(), &mut controllers).join() {
+            if storages.is_sensor(entity) {
+                continue
+            }
+            let (collider, position) = match storages.place(entity, &transforms) {
+                Some(placed) => placed,
+                None => continue,
+            };
+            let displacement = controller.desired_displacement * physics_time.frame_fraction();
+
+            // Placed from the current Transforms, so characters moved later see where this one ended up
+            let bounds = move_bounds(controller, &collider, &position, &displacement).expanded(margin);
+            let nearby: Vec<(Entity, Collider2D, Vector2<f32>)> = storages.near(&broad_phase, &bounds).into_iter()
+                .filter(|other| *other != entity && !storages.is_sensor(*other) && storages.can_collide(entity, *other))
+                .filter_map(|other| {
+                    let (collider, position) = storages.place(other, &transforms)?;
+                    Some((other, collider, position))
+                })
+                .collect();
+
+            let result = {
+                let obstacles: Vec<Obstacle> = nearby.iter()
+                    .map(|(other, collider, position)| Obstacle {
+                        entity: *other,
+                        collider,
+                        position: *position,
+                        displacement: velocities.get(*other).map_or(Vector2::new(0., 0.), |velocity| velocity.0 * delta),
+                        platform: platforms.get(*other),
+                    })
+                    .collect();
+                move_and_slide(controller, &collider, &position, &displacement, &obstacles)
+            };
+            margin = margin.max(result.displacement.norm());
+
+            let displacement = local_displacement(entity, &result.displacement, &transforms, storages.parents());
+            if let Some(transform) = transforms.get_mut(entity) {
+                transform.translate_x(displacement[0]);
+                transform.translate_y(displacement[1]);
+            }
+
+            result.apply_to(controller);
+            // With a fixed time step, the 'FixedStepPhysics' resets it after all steps of the frame
+            if physics_time.fixed_step().is_none() {
+                controller.desired_displacement = Vector2::new(0., 0.);
+            }
+        }
+    }
+}
+
+/// Runs the physics systems in fixed time steps, see 'PhysicsTime'.
+///
+/// Every frame, the frame time is added up and the systems run once for every whole step that fits in.
+/// After a long frame, at most 'max_steps' steps are run, dropping the time that's left,
+/// so the game doesn't fall behind further and further.
+///
+/// Before every step, the 'PreviousTransform' of the entities having one is updated.
+/// After all steps of a frame, the desired displacements of the 'CharacterController2D's are reset.
+/// If no step runs, they're kept for the next frame.
+pub struct FixedStepPhysics {
+    step: f32,
+    /// Most steps run within a single frame.
+    pub max_steps: usize,
+    accumulator: f32,
+    movement: MovementSystem,
+    character_controller: CharacterControllerSystem,
+    collision: CollisionSystem,
+    contact_solver: Option<ContactSolverSystem>,
+    handle_collisions: Option<HandleCollisionsSystem>,
+}
+
+impl FixedStepPhysics {
+    /// Panics if 'step' isn't positive.
+    pub fn new(step: f32, collision: CollisionSystem, contact_solver: Option<ContactSolverSystem>,
+               handle_collisions: Option<HandleCollisionsSystem>) -> Self {
+        assert!(step > 0., "The fixed time step needs to be positive, but is {}", step);
+
+        FixedStepPhysics {
+            step,
+            max_steps: 8,
+            accumulator: 0.,
+            movement: MovementSystem,
+            character_controller: CharacterControllerSystem,
+            collision,
+            contact_solver,
+            handle_collisions,
+        }
+    }
+}
+
+impl<'a> RunNow<'a> for FixedStepPhysics {
+    fn run_now(&mut self, res: &'a Resources) {
+        self.accumulator += res.fetch::<Time>().delta_seconds();
+
+        let steps = ((self.accumulator / self.step).floor() as usize).min(self.max_steps);
+        res.fetch_mut::<PhysicsTime>().set_frame_steps(steps);
+
+        for _ in 0..steps {
+            remember_transforms(res);
+            self.movement.run_now(res);
+            self.character_controller.run_now(res);
+            self.collision.run_now(res);
+            if let Some(contact_solver) = self.contact_solver.as_mut() {
+                contact_solver.run_now(res);
+            }
+            if let Some(handle_collisions) = self.handle_collisions.as_mut() {
+                handle_collisions.run_now(res);
+            }
+
+            self.accumulator -= self.step;
+        }
+        if self.accumulator >= self.step {
+            self.accumulator %= self.step;
+        }
+
+        if steps > 0 {
+            let mut controllers = WriteStorage::<CharacterController2D>::fetch(res);
+            for controller in (&mut controllers).join() {
+                controller.desired_displacement = Vector2::new(0., 0.);
+            }
+        }
 
-            let (velx, vely) = (velocity.0[0], velocity.0[1]);
+        res.fetch_mut::<PhysicsTime>().set_alpha(self.accumulator / self.step);
+    }
 
-            transform.translate_x(velx * delta);
-            transform.translate_y(vely * delta);
+    fn setup(&mut self, res: &mut Resources) {
+        RunNow::setup(&mut self.movement, res);
+        RunNow::setup(&mut self.character_controller, res);
+        RunNow::setup(&mut self.collision, res);
+        if let Some(contact_solver) = self.contact_solver.as_mut() {
+            RunNow::setup(contact_solver, res);
         }
+        if let Some(handle_collisions) = self.handle_collisions.as_mut() {
+            RunNow::setup(handle_collisions, res);
+        }
+        <WriteStorage<'a, PreviousTransform> as SystemData>::setup(res);
+
+        res.fetch_mut::<PhysicsTime>().set_fixed_step(self.step);
+    }
+}
+
+/// Copies the Transforms of the entities with a 'PreviousTransform' into it.
+fn remember_transforms(res: &Resources) {
+    let (transforms, mut previous) = <(ReadStorage<Transform>, WriteStorage<PreviousTransform>) as SystemData>::fetch(res);
+    for (transform, previous) in (&transforms, &mut previous).join() {
+        previous.0 = transform.clone();
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test_systems {
+    use crate::systems::{FixedStepPhysics, CollisionSystem, accumulate_push};
+    use crate::components::{PhysicsTime, Velocity, PreviousTransform, CharacterController2D, Collider2D};
+    use crate::events::{CollisionEvent, CollisionKind};
+    use amethyst::core::{nalgebra::Vector2, timing::Time, transform::Transform, shrev::{EventChannel, ReaderId}};
+    use amethyst::ecs::{World, Builder, Entity, RunNow};
+
+    fn transform_at(x: f32, y: f32) -> Transform {
+        let mut transform = Transform::default();
+        transform.translate_x(x);
+        transform.translate_y(y);
+        transform
+    }
+
+    fn place(world: &mut World, collider: Collider2D, x: f32, y: f32) -> Entity {
+        world.create_entity().with(collider).with(transform_at(x, y)).build()
+    }
+
+    /// Kind and entities of the events sent since the last call.
+    fn read_events(world: &World, reader: &mut ReaderId<CollisionEvent>) -> Vec<(CollisionKind, Entity, Entity)> {
+        world.read_resource::<EventChannel<CollisionEvent>>().read(reader)
+            .map(|event| (event.kind, event.collisions[0].entity, event.collisions[1].entity))
+            .collect()
+    }
+
+    #[test]
+    fn test_contact_tracking() {
+        use crate::components::DeactivateCollider;
+
+        let mut world = World::new();
+        let mut collision_system = CollisionSystem::default();
+        RunNow::setup(&mut collision_system, &mut world.res);
+        let mut reader = world.write_resource::<EventChannel<CollisionEvent>>().register_reader();
+
+        let first = place(&mut world, Collider2D::rect_without_offset(2., 2.), 0., 0.);
+        let second = place(&mut world, Collider2D::rect_without_offset(2., 2.), 1.5, 0.);
+
+        collision_system.run_now(&world.res);
+        assert_eq!(read_events(&world, &mut reader), vec![(CollisionKind::Started, first, second)]);
+        collision_system.run_now(&world.res);
+        assert_eq!(read_events(&world, &mut reader), vec![(CollisionKind::Persisting, first, second)]);
+
+        world.write_storage::<Transform>().insert(second, transform_at(5., 0.)).unwrap();
+        collision_system.run_now(&world.res);
+        assert_eq!(read_events(&world, &mut reader), vec![(CollisionKind::Ended, first, second)]);
+        collision_system.run_now(&world.res);
+        assert!(read_events(&world, &mut reader).is_empty());
+
+        // Contacts also end when an entity is deleted or its collider deactivated, ordered by contact key
+        let third = place(&mut world, Collider2D::rect_without_offset(2., 2.), 1.5, 0.);
+        let fourth = place(&mut world, Collider2D::rect_without_offset(2., 2.), 6.5, 0.);
+        collision_system.run_now(&world.res);
+        assert_eq!(read_events(&world, &mut reader), vec![(CollisionKind::Started, first, third),
+                                                          (CollisionKind::Started, second, fourth)]);
+
+        world.write_storage::<DeactivateCollider>().insert(second, DeactivateCollider).unwrap();
+        world.delete_entity(third).unwrap();
+        world.maintain();
+        collision_system.run_now(&world.res);
+        assert_eq!(read_events(&world, &mut reader), vec![(CollisionKind::Ended, first, third),
+                                                          (CollisionKind::Ended, second, fourth)]);
+    }
+
+    #[test]
+    fn test_sensors() {
+        use crate::systems::{HandleCollisionsSystem, ContactSolverSystem};
+        use crate::components::{Sensor, HandleCollisionMode, PassiveCollider};
+
+        let mut world = World::new();
+        let mut collision_system = CollisionSystem::default();
+        let mut contact_solver = ContactSolverSystem::default();
+        let mut handle_collisions = HandleCollisionsSystem::default();
+        RunNow::setup(&mut collision_system, &mut world.res);
+        RunNow::setup(&mut contact_solver, &mut world.res);
+        RunNow::setup(&mut handle_collisions, &mut world.res);
+        world.write_resource::<Time>().set_delta_seconds(0.1);
+        let mut reader = world.write_resource::<EventChannel<CollisionEvent>>().register_reader();
+
+        let sensor = place(&mut world, Collider2D::rect_without_offset(2., 2.), 0., 0.);
+        let ball = place(&mut world, Collider2D::circle_without_offset(1.), 1.5, 0.);
+        world.write_storage::<Sensor>().insert(sensor, Sensor).unwrap();
+        for (entity, velocity, mode) in vec![(sensor, 1., HandleCollisionMode::Impulse),
+                                             (ball, -1., HandleCollisionMode::Reflect)] {
+            world.write_storage::<Velocity>().insert(entity, Velocity(Vector2::new(velocity, 0.))).unwrap();
+            world.write_storage::<HandleCollisionMode>().insert(entity, mode).unwrap();
+        }
+
+        // A solid pair to tell that the collisions got handled at all
+        let crate_entity = place(&mut world, Collider2D::rect_without_offset(2., 2.), 10., 0.);
+        let wall = place(&mut world, Collider2D::rect_without_offset(2., 2.), 11.5, 0.);
+        world.write_storage::<PassiveCollider>().insert(wall, PassiveCollider).unwrap();
+        world.write_storage::<Velocity>().insert(crate_entity, Velocity(Vector2::new(1., 0.))).unwrap();
+        world.write_storage::<HandleCollisionMode>().insert(crate_entity, HandleCollisionMode::Reflect).unwrap();
+
+        collision_system.run_now(&world.res);
+        contact_solver.run_now(&world.res);
+        handle_collisions.run_now(&world.res);
+
+        let sensor_events: Vec<bool> = world.read_resource::<EventChannel<CollisionEvent>>().read(&mut reader)
+            .map(|event| event.is_sensor)
+            .collect();
+        assert_eq!(sensor_events, vec![true, false]);
+
+        let velocities = world.read_storage::<Velocity>();
+        let transforms = world.read_storage::<Transform>();
+        assert_eq!(velocities.get(sensor).unwrap().0, Vector2::new(1., 0.));
+        assert_eq!(velocities.get(ball).unwrap().0, Vector2::new(-1., 0.));
+        assert_eq!(transforms.get(sensor).unwrap().translation()[0], 0.);
+        assert_eq!(transforms.get(ball).unwrap().translation()[0], 1.5);
+
+        assert!(velocities.get(crate_entity).unwrap().0[0] < 0.);
+        assert!((transforms.get(crate_entity).unwrap().translation()[0] - 9.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_depenetration_shares() {
+        use crate::systems::HandleCollisionsSystem;
+        use crate::components::{HandleCollisionMode, PassiveCollider};
+
+        let mut world = World::new();
+        let mut collision_system = CollisionSystem::default();
+        let mut handle_collisions = HandleCollisionsSystem::default();
+        RunNow::setup(&mut collision_system, &mut world.res);
+        RunNow::setup(&mut handle_collisions, &mut world.res);
+
+        let handled = |world: &mut World, x: f32| {
+            let entity = place(world, Collider2D::rect_without_offset(2., 2.), x, 0.);
+            world.write_storage::<Velocity>().insert(entity, Velocity(Vector2::new(0., 0.))).unwrap();
+            world.write_storage::<HandleCollisionMode>().insert(entity, HandleCollisionMode::Reflect).unwrap();
+            entity
+        };
+
+        // Both handled, 0.5 deep
+        let (left, right) = (handled(&mut world, 0.), handled(&mut world, 1.5));
+        // Against a passive platform that gets handled itself
+        let (body, platform) = (handled(&mut world, 10.), handled(&mut world, 11.5));
+        world.write_storage::<PassiveCollider>().insert(platform, PassiveCollider).unwrap();
+        // Against a collider that doesn't get handled
+        let lonely = handled(&mut world, 20.);
+        place(&mut world, Collider2D::rect_without_offset(2., 2.), 21.5, 0.);
+
+        collision_system.run_now(&world.res);
+        handle_collisions.run_now(&world.res);
+
+        let transforms = world.read_storage::<Transform>();
+        let x = |entity: Entity| transforms.get(entity).unwrap().translation()[0];
+        assert!((x(left) + 0.25).abs() < 1e-4);
+        assert!((x(right) - 1.75).abs() < 1e-4);
+        assert!((x(body) - 9.5).abs() < 1e-4);
+        assert_eq!(x(platform), 11.5);
+        assert!((x(lonely) - 19.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_continuous_collision() {
+        use crate::systems::MovementSystem;
+        use crate::components::{ContinuousCollision, OneWayPlatform};
+
+        let mut world = World::new();
+        let mut movement_system = MovementSystem;
+        let mut collision_system = CollisionSystem::default();
+        RunNow::setup(&mut movement_system, &mut world.res);
+        RunNow::setup(&mut collision_system, &mut world.res);
+        world.write_resource::<Time>().set_delta_seconds(1. / 60.);
+        let mut reader = world.write_resource::<EventChannel<CollisionEvent>>().register_reader();
+
+        let bullet = |world: &mut World, x: f32, y: f32, velocity: Vector2<f32>| {
+            let entity = place(world, Collider2D::rect_without_offset(4., 4.), x, y);
+            world.write_storage::<Velocity>().insert(entity, Velocity(velocity)).unwrap();
+            world.write_storage::<ContinuousCollision>().insert(entity, ContinuousCollision).unwrap();
+            entity
+        };
+
+        // 50 pixels a frame would skip the 16 pixel wall
+        let fast = bullet(&mut world, 0., 0., Vector2::new(3000., 0.));
+        let wall = place(&mut world, Collider2D::rect_without_offset(16., 100.), 30., 0.);
+        // Moves less than half its size, so it just ends up overlapping the wall
+        let slow = bullet(&mut world, 19., 200., Vector2::new(90., 0.));
+        let slow_wall = place(&mut world, Collider2D::rect_without_offset(16., 100.), 30., 200.);
+        // Passes through the platform from below
+        let rising = bullet(&mut world, 0., 400., Vector2::new(0., 3000.));
+        let platform = place(&mut world, Collider2D::rect_without_offset(100., 4.), 0., 420.);
+        world.write_storage::<OneWayPlatform>().insert(platform, OneWayPlatform::new(Vector2::new(0., 1.))).unwrap();
+
+        collision_system.run_now(&world.res);
+        assert!(read_events(&world, &mut reader).is_empty());
+
+        movement_system.run_now(&world.res);
+        {
+            let transforms = world.read_storage::<Transform>();
+            let translation = |entity: Entity| transforms.get(entity).unwrap().translation().clone();
+            assert!((translation(fast)[0] - 20.).abs() < 0.05);
+            assert!((translation(slow)[0] - 20.5).abs() < 1e-3);
+            assert!((translation(rising)[1] - 450.).abs() < 1e-3);
+        }
+
+        collision_system.run_now(&world.res);
+        assert_eq!(read_events(&world, &mut reader), vec![(CollisionKind::Started, fast, wall),
+                                                          (CollisionKind::Started, slow, slow_wall)]);
+    }
+
+    #[test]
+    fn test_fixed_step() {
+        let mut world = World::new();
+        world.add_resource(Time::default());
+
+        let mut physics = FixedStepPhysics::new(0.02, CollisionSystem::default(), None, None);
+        RunNow::setup(&mut physics, &mut world.res);
+        let entity = world.create_entity()
+            .with(Transform::default())
+            .with(Velocity(Vector2::new(10., 0.)))
+            .build();
+
+        world.write_resource::<Time>().set_delta_seconds(0.05);
+        physics.run_now(&world.res);
+
+        // Two steps of 0.02 seconds, half a step is left
+        let x = world.read_storage::<Transform>().get(entity).unwrap().translation()[0];
+        assert!((x - 0.4).abs() < 1e-4);
+        assert!((world.read_resource::<PhysicsTime>().alpha() - 0.5).abs() < 1e-4);
+        assert_eq!(world.read_resource::<PhysicsTime>().fixed_step(), Some(0.02));
+
+        // Too long frames don't run more than 'max_steps' steps
+        world.write_resource::<Time>().set_delta_seconds(10.);
+        physics.run_now(&world.res);
+        let x = world.read_storage::<Transform>().get(entity).unwrap().translation()[0];
+        assert!((x - 0.4 - 0.02 * 8. * 10.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_fixed_step_character() {
+        let mut world = World::new();
+        world.add_resource(Time::default());
+
+        let mut physics = FixedStepPhysics::new(0.02, CollisionSystem::default(), None, None);
+        RunNow::setup(&mut physics, &mut world.res);
+        let mut controller = CharacterController2D::default();
+        controller.desired_displacement = Vector2::new(1., 0.);
+        let entity = world.create_entity()
+            .with(Transform::default())
+            .with(Collider2D::rect_without_offset(1., 1.))
+            .with(controller)
+            .with(PreviousTransform::default())
+            .build();
+
+        // Each of the two steps moves the character by half of the frame's displacement
+        world.write_resource::<Time>().set_delta_seconds(0.05);
+        physics.run_now(&world.res);
+        let x = world.read_storage::<Transform>().get(entity).unwrap().translation()[0];
+        assert!((x - 1.).abs() < 1e-4);
+        assert_eq!(world.read_storage::<CharacterController2D>().get(entity).unwrap().desired_displacement,
+                   Vector2::new(0., 0.));
+
+        // Remembered before the second step
+        let previous = world.read_storage::<PreviousTransform>().get(entity).unwrap().0.translation()[0];
+        assert!((previous - 0.5).abs() < 1e-4);
+
+        // A frame shorter than a step runs no step, the displacement waits for the next one
+        world.write_storage::<CharacterController2D>().get_mut(entity).unwrap()
+            .desired_displacement = Vector2::new(1., 0.);
+        world.write_resource::<Time>().set_delta_seconds(0.005);
+        physics.run_now(&world.res);
+        let x = world.read_storage::<Transform>().get(entity).unwrap().translation()[0];
+        assert!((x - 1.).abs() < 1e-4);
+        assert_eq!(world.read_storage::<CharacterController2D>().get(entity).unwrap().desired_displacement,
+                   Vector2::new(1., 0.));
+
+        world.write_storage::<CharacterController2D>().get_mut(entity).unwrap()
+            .desired_displacement += Vector2::new(1., 0.);
+        world.write_resource::<Time>().set_delta_seconds(0.01);
+        physics.run_now(&world.res);
+        let x = world.read_storage::<Transform>().get(entity).unwrap().translation()[0];
+        assert!((x - 3.).abs() < 1e-4);
+        assert_eq!(world.read_resource::<PhysicsTime>().frame_steps(), 1);
+    }
+
+    #[test]
+    fn test_character_controller_broad_phase() {
+        use crate::systems::CharacterControllerSystem;
+
+        let mut world = World::new();
+        let mut collision_system = CollisionSystem::default();
+        let mut character_system = CharacterControllerSystem;
+        RunNow::setup(&mut collision_system, &mut world.res);
+        RunNow::setup(&mut character_system, &mut world.res);
+
+        place(&mut world, Collider2D::rect_without_offset(100., 2.), 0., 0.);
+        let character = place(&mut world, Collider2D::rect_without_offset(1., 2.), 0., 5.);
+        world.write_storage::<CharacterController2D>()
+            .insert(character, CharacterController2D::default()).unwrap();
+        collision_system.run_now(&world.res);
+
+        // Created after the broad phase was updated, but still in the way
+        let wall = place(&mut world, Collider2D::rect_without_offset(1., 20.), 3., 5.);
+        // Far away from the move
+        place(&mut world, Collider2D::rect_without_offset(1., 1.), 50., 50.);
+
+        world.write_storage::<CharacterController2D>().get_mut(character).unwrap()
+            .desired_displacement = Vector2::new(3., -10.);
+        character_system.run_now(&world.res);
+
+        let translation = world.read_storage::<Transform>().get(character).unwrap().translation().clone();
+        assert!((translation[0] - 1.98).abs() < 0.05);
+        assert!((translation[1] - 2.02).abs() < 0.05);
+        let controllers = world.read_storage::<CharacterController2D>();
+        let controller = controllers.get(character).unwrap();
+        assert!(controller.grounded && controller.on_wall);
+        assert!(controller.touched.contains(&wall));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_fixed_step_not_positive() {
+        FixedStepPhysics::new(0., CollisionSystem::default(), None, None);
+    }
+
+    #[test]
+    fn test_accumulate_push() {
+        let mut pushes = Vec::new();
+
+        // Resting across two tiles
+        accumulate_push(&mut pushes, Vector2::new(0., 0.3));
+        accumulate_push(&mut pushes, Vector2::new(0., 0.5));
+        assert_eq!(pushes, vec![Vector2::new(0., 0.5)]);
+
+        // Stuck in a corner
+        accumulate_push(&mut pushes, Vector2::new(-0.2, 0.));
+        accumulate_push(&mut pushes, Vector2::new(0., 0.));
+        assert_eq!(pushes, vec![Vector2::new(0., 0.5), Vector2::new(-0.2, 0.)]);
+    }
+}
@@ -1,17 +1,201 @@
 use amethyst::{
     ecs::{
-        ReadStorage, WriteStorage, SystemData, Resources, shred::ResourceId, Entity
+        ReadStorage, WriteStorage, SystemData, Resources, shred::ResourceId, Entity, Entities, Join,
+        Storage, storage::MaskedStorage,
     },
     core::{
-        nalgebra::Vector2,
+        nalgebra::{Vector2, Matrix4},
+        transform::{Transform, Parent},
     },
 };
 
 use crate::{
-    components::{Velocity, HandleCollisionMode},
-    events::Collision,
+    components::{Velocity, HandleCollisionMode, Collider2D, CollisionGroups, DeactivateCollider, Sensor},
+    broad_phase::BroadPhase,
+    geometry::Aabb,
 };
 
+use std::ops::Deref;
+
+/// Places an entity in world space by combining its Transform with the ones of its parents.
+///
+/// Unlike 'GlobalTransform', this is up to date even if the 'TransformSystem' hasn't run yet
+/// since the entity or one of its parents moved. Returns None if the entity has no Transform.
+pub fn world_matrix<T, P>(entity: Entity, transforms: &Storage<'_, Transform, T>, parents: &Storage<'_, Parent, P>)
+    -> Option<Matrix4<f32>>
+    where
+        T: Deref<Target = MaskedStorage<Transform>>,
+        P: Deref<Target = MaskedStorage<Parent>>,
+{
+    let mut matrix = transforms.get(entity)?.matrix();
+
+    let mut current = entity;
+    while let Some(parent) = parents.get(current) {
+        match transforms.get(parent.entity) {
+            Some(transform) => matrix = transform.matrix() * matrix,
+            None => break,
+        }
+        current = parent.entity;
+    }
+
+    Some(matrix)
+}
+
+/// Converts a displacement in world space into the local space of 'entity's Transform,
+/// i.e. the space of its parent.
+pub fn local_displacement<T, P>(entity: Entity, displacement: &Vector2<f32>,
+                                transforms: &Storage<'_, Transform, T>, parents: &Storage<'_, Parent, P>)
+    -> Vector2<f32>
+    where
+        T: Deref<Target = MaskedStorage<Transform>>,
+        P: Deref<Target = MaskedStorage<Parent>>,
+{
+    let parent_matrix = match parents.get(entity).and_then(|parent| world_matrix(parent.entity, transforms, parents)) {
+        Some(matrix) => matrix,
+        None => return *displacement,
+    };
+
+    // Invert the 2D part of the parent's rotation and scale
+    let (a, b, c, d) = (parent_matrix[(0, 0)], parent_matrix[(0, 1)], parent_matrix[(1, 0)], parent_matrix[(1, 1)]);
+    let determinant = a * d - b * c;
+    if determinant.abs() <= std::f32::EPSILON {
+        return *displacement
+    }
+
+    Vector2::new(
+        (d * displacement[0] - b * displacement[1]) / determinant,
+        (a * displacement[1] - c * displacement[0]) / determinant,
+    )
+}
+
+type ColliderStoragesData<'a> = (
+    Entities<'a>,
+    ReadStorage<'a, Collider2D>,
+    ReadStorage<'a, Parent>,
+    ReadStorage<'a, DeactivateCollider>,
+    ReadStorage<'a, Sensor>,
+    ReadStorage<'a, CollisionGroups>,
+);
+
+/// All relevant component storages for placing colliders in world space.
+///
+/// The Transforms are passed separately, as some systems need to write them.
+pub struct ColliderStorages<'a> {
+    entities: Entities<'a>,
+    colliders: ReadStorage<'a, Collider2D>,
+    parents: ReadStorage<'a, Parent>,
+    deactivations: ReadStorage<'a, DeactivateCollider>,
+    sensors: ReadStorage<'a, Sensor>,
+    groups: ReadStorage<'a, CollisionGroups>,
+}
+
+impl<'a> SystemData<'a> for ColliderStorages<'a> {
+    fn setup(res: &mut Resources) {
+        <ColliderStoragesData<'a> as SystemData>::setup(res);
+    }
+
+    fn fetch(res: &'a Resources) -> Self {
+        let (entities, colliders, parents, deactivations, sensors, groups) =
+            <ColliderStoragesData<'a> as SystemData<'a>>::fetch(res);
+
+        ColliderStorages {
+            entities, colliders, parents, deactivations, sensors, groups,
+        }
+    }
+
+    fn reads() -> Vec<ResourceId> {
+        <ColliderStoragesData as SystemData>::reads()
+    }
+
+    fn writes() -> Vec<ResourceId> {
+        <ColliderStoragesData as SystemData>::writes()
+    }
+}
+
+impl<'a> ColliderStorages<'a> {
+    pub fn entities(&self) -> &Entities<'a> {
+        &self.entities
+    }
+
+    pub fn parents(&self) -> &ReadStorage<'a, Parent> {
+        &self.parents
+    }
+
+    /// The collider of 'entity' in world space and its position, see 'world_matrix'.
+    ///
+    /// Returns None if the entity has no Collider2D or Transform, or its collider is deactivated.
+    pub fn place<T>(&self, entity: Entity, transforms: &Storage<'_, Transform, T>) -> Option<(Collider2D, Vector2<f32>)>
+        where
+            T: Deref<Target = MaskedStorage<Transform>>,
+    {
+        if self.deactivations.contains(entity) {
+            return None
+        }
+        let collider = self.colliders.get(entity)?;
+        let matrix = world_matrix(entity, transforms, &self.parents)?;
+        Some(collider.transformed_by_matrix(&matrix))
+    }
+
+    /// Every active collider in world space, with its entity and position.
+    /// Sensors are left out, unless 'include_sensors' is set.
+    pub fn placed<T>(&self, transforms: &Storage<'_, Transform, T>, include_sensors: bool)
+        -> Vec<(Entity, Collider2D, Vector2<f32>)>
+        where
+            T: Deref<Target = MaskedStorage<Transform>>,
+    {
+        (&self.entities, &self.colliders).join()
+            .filter(|(entity, _)| include_sensors || !self.is_sensor(*entity))
+            .filter_map(|(entity, _)| {
+                let (collider, position) = self.place(entity, transforms)?;
+                Some((entity, collider, position))
+            })
+            .collect()
+    }
+
+    /// Entities with a collider that might overlap 'bounds', sorted, looked up in 'broad_phase'.
+    ///
+    /// The broad phase knows the colliders of the last time the 'CollisionSystem' ran. Colliders it
+    /// doesn't know, e.g. because they were created since then or the 'CollisionSystem' never ran,
+    /// are always included. Colliders teleported since then may still be missed.
+    pub fn near(&self, broad_phase: &BroadPhase, bounds: &Aabb) -> Vec<Entity> {
+        let mut entities = broad_phase.query(bounds);
+        entities.extend((&self.entities, &self.colliders).join()
+            .map(|(entity, _)| entity)
+            .filter(|entity| !broad_phase.contains(*entity)));
+        entities.sort();
+        entities.dedup();
+        entities
+    }
+
+    pub fn is_sensor(&self, entity: Entity) -> bool {
+        self.sensors.contains(entity)
+    }
+
+    /// The 'CollisionGroups' of 'entity', the default ones if it has none.
+    pub fn groups(&self, entity: Entity) -> CollisionGroups {
+        self.groups.get(entity).cloned().unwrap_or_default()
+    }
+
+    /// Whether the 'CollisionGroups' of both entities let them collide.
+    pub fn can_collide(&self, first: Entity, second: Entity) -> bool {
+        self.groups(first).can_collide_with(&self.groups(second))
+    }
+}
+
+/// Accelerates 'velocity' over 'delta' seconds, then applies damping and limits it to 'max_speed'.
+pub fn integrate_velocity(velocity: &mut Vector2<f32>, acceleration: &Vector2<f32>, damping: f32,
+                          max_speed: Option<f32>, delta: f32) {
+    *velocity += acceleration * delta;
+    *velocity /= 1. + damping * delta;
+
+    if let Some(max_speed) = max_speed {
+        let speed = velocity.norm();
+        if speed > max_speed {
+            *velocity *= max_speed / speed;
+        }
+    }
+}
+
 /// Bounces 'velocity' off of a surface, defined by it's'normal'.
 /// Velocity is pointing towards the object beforehand and away from it afterwards.
 pub fn reflect_mut(velocity: &mut Vector2<f32>, normal: &Vector2<f32>) {
@@ -34,7 +218,7 @@ pub fn reflect_velocity(velocity: &mut Velocity, collision: &Vector2<f32>) {
     reflect_mut(&mut velocity.0, &-collision);
 }
 
-fn rotate_vec(vec: &Vector2<f32>, angle: f32) -> Vector2<f32> {
+pub(crate) fn rotate_vec(vec: &Vector2<f32>, angle: f32) -> Vector2<f32> {
     let sin = angle.sin();
     let cos = angle.cos();
 
@@ -49,25 +233,78 @@ pub fn oppose_collision(velocity: &mut Velocity, other_collision: &Vector2<f32>)
     velocity.0 = other_collision.normalize() * velocity.0.norm();
 }
 
-/// Modify components based on the collision mode and the collision paths.
-pub fn handle_collision(collision: &Collision, other_collision: &Collision,
-                        components: HandleCollisionComponents<'_>) {
+/// Modify components based on the collision mode and the contact normal.
+///
+/// 'normal' points from the entity the components belong to towards its collision partner.
+pub fn handle_collision(normal: &Vector2<f32>, components: HandleCollisionComponents<'_>) {
     let velocity = components.velocity;
 
+    // Entities that already move away from the collision partner don't need to change course
+    let approaching = velocity.0.dot(normal) > 0.;
+
     match components.mode {
         HandleCollisionMode::Ignore => return,
-        HandleCollisionMode::Reflect => reflect_velocity(velocity, &collision.path),
-        HandleCollisionMode::Bounce(bounciness) => {
-            reflect_velocity(velocity, &collision.path);
+        HandleCollisionMode::Reflect => if approaching {
+            reflect_velocity(velocity, normal)
+        },
+        HandleCollisionMode::Bounce(bounciness) => if approaching {
+            reflect_velocity(velocity, normal);
             velocity.0[0] = velocity.0[0] * bounciness;
             velocity.0[1] = velocity.0[1] * bounciness;
         }
         HandleCollisionMode::Oppose => {
-            oppose_collision(velocity, &other_collision.path);
+            oppose_collision(velocity, &-*normal);
         }
+        // Needs both collision partners, see 'resolve_impulse'
+        HandleCollisionMode::Impulse => return,
     }
 }
 
+/// One side of a collision resolved by 'resolve_impulse'.
+#[derive(Clone, Debug)]
+pub struct ImpulseBody {
+    pub velocity: Vector2<f32>,
+    /// Zero for immovable bodies.
+    pub inverse_mass: f32,
+    pub restitution: f32,
+    pub friction: f32,
+}
+
+/// Applies an impulse along the contact normal and a friction impulse along the tangent to both bodies.
+///
+/// 'normal' points from the first body towards the second one. Returns the new velocities of both bodies.
+/// Bodies that already move apart, or are both immovable, keep their velocities.
+pub fn resolve_impulse(first: &ImpulseBody, second: &ImpulseBody, normal: &Vector2<f32>)
+    -> (Vector2<f32>, Vector2<f32>)
+{
+    let inverse_mass_sum = first.inverse_mass + second.inverse_mass;
+    let relative = second.velocity - first.velocity;
+    let normal_speed = relative.dot(normal);
+    if inverse_mass_sum <= 0. || normal_speed >= 0. {
+        return (first.velocity, second.velocity)
+    }
+
+    let restitution = first.restitution.max(second.restitution);
+    let impulse = -(1. + restitution) * normal_speed / inverse_mass_sum;
+    let mut first_velocity = first.velocity - normal * (impulse * first.inverse_mass);
+    let mut second_velocity = second.velocity + normal * (impulse * second.inverse_mass);
+
+    // Friction can't take away more than the normal impulse times the coefficient
+    let relative = second_velocity - first_velocity;
+    let tangent = relative - normal * relative.dot(normal);
+    let tangent_speed = tangent.norm();
+    if tangent_speed > std::f32::EPSILON {
+        let tangent = tangent / tangent_speed;
+        let max_friction = (first.friction * second.friction).sqrt() * impulse;
+        let friction_impulse = (-tangent_speed / inverse_mass_sum).max(-max_friction);
+
+        first_velocity -= tangent * (friction_impulse * first.inverse_mass);
+        second_velocity += tangent * (friction_impulse * second.inverse_mass);
+    }
+
+    (first_velocity, second_velocity)
+}
+
 /// All relevant components for handling collisions.
 ///
 /// Meant to be passed to the 'handle_collision' function.
@@ -130,4 +367,135 @@ impl<'a> HandleCollisionStorages<'a> {
             mode, velocity
         })
     }
-}
\ No newline at end of file
+
+    pub fn mode(&self, entity: Entity) -> Option<&HandleCollisionMode> {
+        self.modes.get(entity)
+    }
+
+    pub fn velocity(&self, entity: Entity) -> Option<&Velocity> {
+        self.velocities.get(entity)
+    }
+
+    pub fn velocity_mut(&mut self, entity: Entity) -> Option<&mut Velocity> {
+        self.velocities.get_mut(entity)
+    }
+
+    /// Whether collisions of this entity get handled, i.e. it has a 'Velocity'
+    /// and a 'HandleCollisionMode' other than 'Ignore'.
+    pub fn handles(&self, entity: Entity) -> bool {
+        match (self.modes.get(entity), self.velocities.get(entity)) {
+            (Some(HandleCollisionMode::Ignore), _) | (_, None) | (None, _) => false,
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_utils {
+    use crate::utils::{resolve_impulse, integrate_velocity, ImpulseBody, world_matrix, local_displacement};
+    use crate::components::Collider2D;
+    use amethyst::core::{nalgebra::Vector2, transform::{Transform, Parent}};
+    use amethyst::ecs::{World, Builder};
+
+    fn body(x: f32, y: f32, inverse_mass: f32, restitution: f32, friction: f32) -> ImpulseBody {
+        ImpulseBody {
+            velocity: Vector2::new(x, y),
+            inverse_mass,
+            restitution,
+            friction,
+        }
+    }
+
+    #[test]
+    fn test_resolve_impulse() {
+        let normal = Vector2::new(1., 0.);
+
+        // Equal masses swap their velocities in an elastic collision
+        let (first, second) = resolve_impulse(&body(1., 0., 1., 1., 0.), &body(-1., 0., 1., 1., 0.), &normal);
+        assert_eq!(first, Vector2::new(-1., 0.));
+        assert_eq!(second, Vector2::new(1., 0.));
+
+        // A light ball barely moves a heavy crate
+        let (first, second) = resolve_impulse(&body(10., 0., 1., 0., 0.), &body(0., 0., 0.01, 0., 0.), &normal);
+        assert!(first[0] > 0. && first[0] < 0.2);
+        assert!(second[0] > 0. && second[0] < 0.1);
+
+        // Immovable partner
+        let (first, second) = resolve_impulse(&body(10., 0., 1., 0.5, 0.), &body(0., 0., 0., 0., 0.), &normal);
+        assert_eq!(first, Vector2::new(-5., 0.));
+        assert_eq!(second, Vector2::new(0., 0.));
+
+        // Already moving apart
+        let (first, _) = resolve_impulse(&body(-1., 0., 1., 0., 0.), &body(0., 0., 1., 0., 0.), &normal);
+        assert_eq!(first, Vector2::new(-1., 0.));
+    }
+
+    #[test]
+    fn test_friction() {
+        let floor = Vector2::new(0., -1.);
+
+        // Friction is limited by the normal impulse
+        let (first, _) = resolve_impulse(&body(1., -1., 1., 0., 0.5), &body(0., 0., 0., 0., 0.5), &floor);
+        assert_eq!(first, Vector2::new(0.5, 0.));
+
+        // Enough friction stops the sliding completely
+        let (first, _) = resolve_impulse(&body(1., -1., 1., 0., 2.), &body(0., 0., 0., 0., 2.), &floor);
+        assert_eq!(first, Vector2::new(0., 0.));
+    }
+
+    #[test]
+    fn test_integrate_velocity() {
+        let gravity = Vector2::new(0., -10.);
+
+        let mut velocity = Vector2::new(2., 0.);
+        integrate_velocity(&mut velocity, &gravity, 0., None, 0.5);
+        assert_eq!(velocity, Vector2::new(2., -5.));
+
+        integrate_velocity(&mut velocity, &Vector2::new(0., 0.), 1., None, 1.);
+        assert_eq!(velocity, Vector2::new(1., -2.5));
+
+        let mut velocity = Vector2::new(30., 40.);
+        integrate_velocity(&mut velocity, &Vector2::new(0., 0.), 0., Some(10.), 1.);
+        assert_eq!(velocity, Vector2::new(6., 8.));
+    }
+
+    #[test]
+    fn test_parent_placement() {
+        let mut world = World::new();
+        world.register::<Transform>();
+        world.register::<Parent>();
+
+        // Turned by 90 degrees and scaled up twice
+        let mut parent_transform = Transform::default();
+        parent_transform.set_rotation_euler(0., 0., std::f32::consts::PI * 0.5);
+        parent_transform.set_scale(2., 2., 1.);
+        parent_transform.translate_x(10.);
+        let parent = world.create_entity().with(parent_transform).build();
+
+        let mut child_transform = Transform::default();
+        child_transform.translate_x(1.);
+        let child = world.create_entity()
+            .with(child_transform)
+            .with(Parent { entity: parent })
+            .build();
+        let orphan = world.create_entity().build();
+
+        let transforms = world.read_storage::<Transform>();
+        let parents = world.read_storage::<Parent>();
+
+        let matrix = world_matrix(child, &transforms, &parents).unwrap();
+        assert!((Vector2::new(matrix[(0, 3)], matrix[(1, 3)]) - Vector2::new(10., 2.)).norm() < 1e-5);
+        assert!(world_matrix(orphan, &transforms, &parents).is_none());
+
+        // The child's collider is stretched and turned like its parent
+        let (collider, position) = Collider2D::rect_without_offset(1., 2.).transformed_by_matrix(&matrix);
+        let aabb = collider.aabb(&position);
+        assert!((aabb.min - Vector2::new(8., 1.)).norm() < 1e-4);
+        assert!((aabb.max - Vector2::new(12., 3.)).norm() < 1e-4);
+
+        // Pushing the child up in the world moves it along its parent's x-axis
+        let local = local_displacement(child, &Vector2::new(0., 4.), &transforms, &parents);
+        assert!((local - Vector2::new(2., 0.)).norm() < 1e-5);
+        assert_eq!(local_displacement(parent, &Vector2::new(0., 4.), &transforms, &parents), Vector2::new(0., 4.));
+    }
+}